@@ -37,6 +37,7 @@ fn a(bench: &mut Bencher) {
             }),
             None
         ]),
+        ..Default::default()
     };
 
     bench.iter(|| {
@@ -72,6 +73,7 @@ fn b(bench: &mut Bencher) {
             None,
             None
         ]),
+        ..Default::default()
     };
 
     bench.iter(|| {
@@ -0,0 +1,227 @@
+use crate::isomorphism::{canonical_permutation, SuitPermutation};
+use crate::range::sorted_hand;
+use crate::types::{Card, CardSet, HandSolution, Solution, Table, Variant};
+use itertools::Itertools;
+use std::collections::HashMap;
+use std::mem::size_of;
+
+pub const DEFAULT_CACHE_CAPACITY_BYTES: usize = 16 << 20;
+
+/// Table with canonical suits. Tables differing only by suits (and by the order of the board
+/// cards) have the same canonical table, and so the same solution up to the suits.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CanonicalTable {
+    hand: CardSet,
+    board: CardSet,
+    unknown_board_cards: usize,
+    dead: CardSet,
+    players: usize,
+    // hands with bits of their weight, sorted
+    opponent_range: Option<Vec<([Card; 2], u64)>>,
+    known_hands: Vec<[Card; 2]>,
+    variant: Variant,
+}
+
+impl CanonicalTable {
+    /// Canonical table, with the permutation from the table's suits to the canonical ones.
+    pub fn new(table: &Table) -> (CanonicalTable, SuitPermutation) {
+        let board: CardSet = table.board.iter().flatten().collect();
+        let permutation = canonical_permutation(
+            table.hand.iter().collect(),
+            board,
+            table.dead.iter().collect(),
+        );
+        let hand = |&[card1, card2]: &[Card; 2]| {
+            sorted_hand(permutation.card(card1), permutation.card(card2))
+        };
+        let canonical = CanonicalTable {
+            hand: permutation.cards(table.hand.iter().collect()),
+            board: permutation.cards(board),
+            unknown_board_cards: table.board.iter().filter(|c| c.is_none()).count(),
+            dead: permutation.cards(table.dead.iter().collect()),
+            players: table.players,
+            opponent_range: table.opponent_range.as_ref().map(|range| {
+                range
+                    .hands()
+                    .iter()
+                    .map(|h| (hand(&h.hand), h.weight.to_bits()))
+                    .sorted()
+                    .collect()
+            }),
+            known_hands: table.known_hands.iter().map(hand).sorted().collect(),
+            variant: table.variant,
+        };
+        (canonical, permutation)
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+    pub size_bytes: usize,
+}
+
+/// Least recently used solutions, stored with canonical suits, up to the capacity in bytes.
+pub struct SolutionCache {
+    capacity_bytes: usize,
+    entries: HashMap<CanonicalTable, CacheEntry>,
+    // incremented on each access, to find the least recently used entry
+    clock: u64,
+    stats: CacheStats,
+}
+
+struct CacheEntry {
+    solution: Solution,
+    last_used: u64,
+}
+
+impl Default for SolutionCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_CACHE_CAPACITY_BYTES)
+    }
+}
+
+impl SolutionCache {
+    pub fn new(capacity_bytes: usize) -> Self {
+        SolutionCache {
+            capacity_bytes,
+            entries: Default::default(),
+            clock: 0,
+            stats: Default::default(),
+        }
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    /// Cached solution of the table with the table's suits, counted as a hit or a miss.
+    pub fn get(&mut self, table: &Table) -> Option<Solution> {
+        let (key, permutation) = CanonicalTable::new(table);
+        self.clock += 1;
+        match self.entries.get_mut(&key) {
+            Some(entry) => {
+                entry.last_used = self.clock;
+                self.stats.hits += 1;
+                Some(permutation.inverse().solution(entry.solution.clone()))
+            }
+            None => {
+                self.stats.misses += 1;
+                None
+            }
+        }
+    }
+
+    pub fn insert(&mut self, table: &Table, solution: &Solution) {
+        let size = solution_size(solution);
+        if size > self.capacity_bytes {
+            return;
+        }
+        let (key, permutation) = CanonicalTable::new(table);
+        self.clock += 1;
+        let entry = CacheEntry {
+            solution: permutation.solution(solution.clone()),
+            last_used: self.clock,
+        };
+        if let Some(previous) = self.entries.insert(key, entry) {
+            self.stats.size_bytes -= solution_size(&previous.solution);
+        }
+        self.stats.size_bytes += size;
+        while self.stats.size_bytes > self.capacity_bytes {
+            let (oldest, _) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .unwrap();
+            let oldest = oldest.clone();
+            let removed = self.entries.remove(&oldest).unwrap();
+            self.stats.size_bytes -= solution_size(&removed.solution);
+        }
+        self.stats.entries = self.entries.len();
+    }
+}
+
+// approximate memory used by the solution
+fn solution_size(solution: &Solution) -> usize {
+    size_of::<Solution>()
+        + solution
+            .hands
+            .iter()
+            .map(|hand| size_of::<HandSolution>() + size_of_val(hand.hand.as_ref()))
+            .sum::<usize>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solve::{full_deck, solve_with_deck};
+    use futures::executor::block_on;
+    use rstest::rstest;
+
+    fn table(deck: &[Card], hand: [usize; 2], board: [usize; 4]) -> Table {
+        let mut board = board.map(|i| Some(deck[i])).to_vec();
+        board.push(None);
+        Table {
+            hand: hand.map(|i| deck[i]).into(),
+            board: board.into(),
+            ..Default::default()
+        }
+    }
+
+    fn to_json(solution: &Solution) -> String {
+        serde_json::to_string(solution).unwrap()
+    }
+
+    #[rstest]
+    fn suit_swapped_table_hits_cache() {
+        let deck = full_deck();
+        // hearts and spades swapped, deck is ordered by suit
+        let hearts_table = table(&deck, [12, 11], [10, 22, 3, 40]);
+        let spades_table = table(&deck, [38, 37], [36, 22, 29, 40]);
+        let mut cache = SolutionCache::default();
+
+        assert!(cache.get(&hearts_table).is_none());
+        let solution = block_on(solve_with_deck(&hearts_table, &deck)).unwrap();
+        cache.insert(&hearts_table, &solution);
+        let cached = cache.get(&spades_table).unwrap();
+        let expected = block_on(solve_with_deck(&spades_table, &deck)).unwrap();
+
+        assert_eq!(to_json(&cached), to_json(&expected));
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                hits: 1,
+                misses: 1,
+                entries: 1,
+                size_bytes: solution_size(&solution),
+            }
+        );
+    }
+
+    #[rstest]
+    fn evicts_least_recently_used() {
+        let deck = full_deck();
+        let tables = [
+            table(&deck, [12, 11], [10, 22, 3, 40]),
+            table(&deck, [12, 11], [10, 22, 3, 41]),
+            table(&deck, [12, 11], [10, 22, 3, 42]),
+        ];
+        let solutions = tables
+            .iter()
+            .map(|table| block_on(solve_with_deck(table, &deck)).unwrap())
+            .collect_vec();
+        let mut cache = SolutionCache::new(solution_size(&solutions[0]) * 2);
+
+        cache.insert(&tables[0], &solutions[0]);
+        cache.insert(&tables[1], &solutions[1]);
+        assert!(cache.get(&tables[0]).is_some());
+        cache.insert(&tables[2], &solutions[2]);
+
+        assert!(cache.get(&tables[0]).is_some());
+        assert!(cache.get(&tables[1]).is_none());
+        assert!(cache.get(&tables[2]).is_some());
+        assert_eq!(cache.stats().entries, 2);
+    }
+}
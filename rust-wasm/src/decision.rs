@@ -0,0 +1,125 @@
+use crate::types::Solution;
+use anyhow::{bail, Result};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CallSituation {
+    // pot before calling, including opponent's bet
+    pub pot: f64,
+    // opponent's bet that I have to call
+    pub to_call: f64,
+    // percentage of the final pot taken by the house
+    pub rake_percent: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CallDecision {
+    // equity at which calling breaks even
+    pub required_equity: f64,
+    // expected profit of calling compared to folding
+    pub call_ev: f64,
+    // largest opponent's bet (into the same pot before the bet) that is still profitable to call,
+    // not set when calling any bet is profitable
+    pub break_even_bet: Option<f64>,
+}
+
+/// Pot odds and expected value of calling with my equity from the solution.
+pub fn call_decision(solution: &Solution, situation: &CallSituation) -> Result<CallDecision> {
+    call_decision_with_equity(solution.equity(), situation)
+}
+
+pub fn call_decision_with_equity(equity: f64, situation: &CallSituation) -> Result<CallDecision> {
+    let CallSituation {
+        pot,
+        to_call,
+        rake_percent,
+    } = *situation;
+    if !(0.0..=1.0).contains(&equity) {
+        bail!("equity must be between 0 and 1, got {equity}")
+    }
+    // negated, so that NaN fails the checks too
+    if !(to_call > 0.0 && to_call.is_finite()) {
+        bail!("bet to call must be positive, got {to_call}")
+    }
+    if !(pot >= to_call && pot.is_finite()) {
+        bail!("pot {pot} must include the bet to call {to_call}")
+    }
+    if !(rake_percent.is_finite() && (0.0..100.0).contains(&rake_percent)) {
+        bail!("rake must be at least 0% and less than 100%, got {rake_percent}%")
+    }
+
+    let kept = 1.0 - rake_percent / 100.0;
+    let final_pot = (pot + to_call) * kept;
+    // calling bet b into the pot before the bet p wins equity * (p + 2b) * kept and costs b
+    let pot_before_bet = pot - to_call;
+    let bet_share = 2.0 * equity * kept;
+    Ok(CallDecision {
+        required_equity: to_call / final_pot,
+        call_ev: equity * final_pot - to_call,
+        break_even_bet: (bet_share < 1.0)
+            .then(|| equity * kept * pot_before_bet / (1.0 - bet_share)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn situation(pot: f64, to_call: f64, rake_percent: f64) -> CallSituation {
+        CallSituation {
+            pot,
+            to_call,
+            rake_percent,
+        }
+    }
+
+    #[rstest]
+    // pot-sized bet: 100 into 100, calling 100 to win 300
+    #[case(0.25, situation(200.0, 100.0, 0.0), 1.0 / 3.0, -25.0, Some(50.0))]
+    #[case(0.5, situation(200.0, 100.0, 0.0), 1.0 / 3.0, 50.0, None)]
+    // 10% rake leaves 270 of the 300 pot
+    #[case(0.25, situation(200.0, 100.0, 10.0), 100.0 / 270.0, -32.5, Some(22.5 / 0.55))]
+    #[case(0.0, situation(150.0, 50.0, 0.0), 0.25, -50.0, Some(0.0))]
+    fn computes_call_decision(
+        #[case] equity: f64,
+        #[case] situation: CallSituation,
+        #[case] required_equity: f64,
+        #[case] call_ev: f64,
+        #[case] break_even_bet: Option<f64>,
+    ) {
+        let decision = call_decision_with_equity(equity, &situation).unwrap();
+
+        assert!((decision.required_equity - required_equity).abs() < 1e-12);
+        assert!((decision.call_ev - call_ev).abs() < 1e-9);
+        match (decision.break_even_bet, break_even_bet) {
+            (Some(actual), Some(expected)) => assert!((actual - expected).abs() < 1e-9),
+            (actual, expected) => assert_eq!(actual, expected),
+        }
+    }
+
+    #[rstest]
+    fn break_even_bet_has_zero_ev() {
+        let equity = 0.3;
+        let decision = call_decision_with_equity(equity, &situation(60.0, 20.0, 5.0)).unwrap();
+        let bet = decision.break_even_bet.unwrap();
+        let at_break_even =
+            call_decision_with_equity(equity, &situation(40.0 + bet, bet, 5.0)).unwrap();
+
+        assert!(at_break_even.call_ev.abs() < 1e-9);
+        assert!((at_break_even.required_equity - equity).abs() < 1e-12);
+    }
+
+    #[rstest]
+    #[case(1.5, situation(200.0, 100.0, 0.0))]
+    #[case(0.5, situation(200.0, 0.0, 0.0))]
+    #[case(0.5, situation(50.0, 100.0, 0.0))]
+    #[case(0.5, situation(200.0, 100.0, 100.0))]
+    #[case(f64::NAN, situation(200.0, 100.0, 0.0))]
+    #[case(0.5, situation(200.0, f64::NAN, 0.0))]
+    #[case(0.5, situation(f64::NAN, 100.0, 0.0))]
+    #[case(0.5, situation(f64::INFINITY, 100.0, 0.0))]
+    #[case(0.5, situation(200.0, 100.0, f64::NAN))]
+    fn rejects_invalid_input(#[case] equity: f64, #[case] situation: CallSituation) {
+        assert!(call_decision_with_equity(equity, &situation).is_err());
+    }
+}
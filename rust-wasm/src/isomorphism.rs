@@ -0,0 +1,326 @@
+use crate::range::sorted_hand;
+use crate::solve::{sort_hands, RunoutCounts, RunoutEvaluator, TableCards};
+use crate::solver::RunoutRecord;
+use crate::types::{Card, CardSet, HandSolution, Solution, Suit, SUIT_COUNT};
+use itertools::Itertools;
+use std::collections::HashMap;
+use web_time::Instant;
+
+/// Permutation of suits - suit `s` is mapped to `suits[s]`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct SuitPermutation {
+    suits: [Suit; SUIT_COUNT],
+}
+
+impl SuitPermutation {
+    pub const IDENTITY: SuitPermutation = SuitPermutation { suits: Suit::ALL };
+
+    pub fn all() -> impl Iterator<Item = SuitPermutation> {
+        Suit::ALL
+            .into_iter()
+            .permutations(SUIT_COUNT)
+            .map(|suits| SuitPermutation {
+                suits: suits.try_into().unwrap(),
+            })
+    }
+
+    pub fn suit(&self, suit: Suit) -> Suit {
+        self.suits[suit as usize]
+    }
+
+    pub fn card(&self, card: Card) -> Card {
+        Card {
+            rank: card.rank,
+            suit: self.suit(card.suit),
+        }
+    }
+
+    pub fn cards(&self, cards: CardSet) -> CardSet {
+        cards.permute_suits(&self.suits)
+    }
+
+    /// Two-card hand with permuted suits, sorted the same way as candidate hands.
+    pub fn hand(&self, hand: &[Card]) -> Box<[Card]> {
+        match *hand {
+            [card1, card2] => sorted_hand(self.card(card1), self.card(card2)).into(),
+            _ => hand.iter().map(|&card| self.card(card)).collect(),
+        }
+    }
+
+    pub fn inverse(&self) -> SuitPermutation {
+        let mut suits = Suit::ALL;
+        for suit in Suit::ALL {
+            suits[self.suit(suit) as usize] = suit;
+        }
+        SuitPermutation { suits }
+    }
+
+    /// Solution with suits of all candidate hands permuted.
+    pub fn solution(&self, solution: Solution) -> Solution {
+        let mut hands = solution.hands.into_vec();
+        for hand in &mut hands {
+            hand.hand = self.hand(&hand.hand);
+        }
+        sort_hands(&mut hands);
+        Solution {
+            hands: hands.into(),
+            ..solution
+        }
+    }
+
+    pub(crate) fn table_cards(&self, table_cards: &TableCards) -> TableCards {
+        TableCards {
+            my_cards: self.cards(table_cards.my_cards),
+            fixed_board_cards: self.cards(table_cards.fixed_board_cards),
+            remaining_deck: table_cards
+                .remaining_deck
+                .iter()
+                .map(|&card| self.card(card))
+                .collect(),
+            choose: table_cards.choose,
+            opponents: table_cards.opponents,
+            variant: table_cards.variant,
+            hands: table_cards
+                .hands
+                .iter()
+                .map(|hand| HandSolution {
+                    hand: self.hand(&hand.hand),
+                    ..hand.clone()
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Permutation mapping the cards to their canonical suits, so that tables which differ only by
+/// suits are mapped to the same cards. Canonical suits are ordered (hearts, diamonds, spades,
+/// clubs) by the ranks they have in the hand, then on the board, then in the dead cards.
+pub fn canonical_permutation(hand: CardSet, board: CardSet, dead: CardSet) -> SuitPermutation {
+    let suit_ranks = |cards: CardSet| Suit::ALL.map(|suit| cards.suit_ranks(suit));
+    SuitPermutation::all()
+        .max_by_key(|permutation| {
+            (
+                suit_ranks(permutation.cards(hand)),
+                suit_ranks(permutation.cards(board)),
+                suit_ranks(permutation.cards(dead)),
+            )
+        })
+        .unwrap()
+}
+
+/// Evaluates only one runout of each set of runouts that differ just by suits the table doesn't
+/// distinguish (e.g. the 3 suits not in my suited hand pre-flop), and counts it for all of them.
+pub(crate) struct SymmetricEvaluator<'a> {
+    evaluator: RunoutEvaluator<'a>,
+    opponents: usize,
+    // permutations keeping the table the same, with index of each candidate hand after permutation
+    symmetries: Vec<(SuitPermutation, Vec<usize>)>,
+    // outcomes of the evaluated runout, scratch counts are not used
+    record: RunoutRecord,
+    scratch: RunoutCounts,
+}
+
+impl<'a> SymmetricEvaluator<'a> {
+    pub(crate) fn new(table_cards: &'a TableCards) -> Self {
+        let hand_indexes: HashMap<&[Card], usize> = table_cards
+            .hands
+            .iter()
+            .enumerate()
+            .map(|(i, hand)| (hand.hand.as_ref(), i))
+            .collect();
+        let remaining_cards: CardSet = table_cards.remaining_deck.iter().collect();
+        let symmetries = SuitPermutation::all()
+            .filter(|permutation| {
+                permutation.cards(table_cards.my_cards) == table_cards.my_cards
+                    && permutation.cards(table_cards.fixed_board_cards)
+                        == table_cards.fixed_board_cards
+                    && permutation.cards(remaining_cards) == remaining_cards
+            })
+            .filter_map(|permutation| {
+                let hand_permutation = table_cards
+                    .hands
+                    .iter()
+                    .map(|hand| {
+                        let &i = hand_indexes.get(permutation.hand(&hand.hand).as_ref())?;
+                        // opponent's range has to be symmetric too
+                        (table_cards.hands[i].weight == hand.weight).then_some(i)
+                    })
+                    .collect::<Option<Vec<_>>>()?;
+                Some((permutation, hand_permutation))
+            })
+            .collect_vec();
+        SymmetricEvaluator {
+            evaluator: RunoutEvaluator::new(table_cards),
+            opponents: table_cards.opponents,
+            symmetries,
+            record: RunoutRecord::new(table_cards.hands.len()),
+            scratch: RunoutCounts::new(table_cards),
+        }
+    }
+
+    pub(crate) fn set_deadline(&mut self, deadline: Instant) {
+        self.evaluator.set_deadline(deadline);
+    }
+
+    pub(crate) fn deadline_exceeded(&self) -> bool {
+        self.evaluator.deadline_exceeded()
+    }
+
+    /// Whether the runout is evaluated, and counted for all its symmetric runouts. The runout with
+    /// the lowest bits stands for all its symmetric runouts.
+    pub(crate) fn is_representative(&self, fill_cards: CardSet) -> bool {
+        self.symmetries
+            .iter()
+            .all(|(permutation, _)| permutation.cards(fill_cards) >= fill_cards)
+    }
+
+    pub(crate) fn evaluate(&mut self, fill_cards: CardSet, counts: &mut RunoutCounts) {
+        if self.symmetries.len() == 1 {
+            self.evaluator.evaluate(fill_cards, counts, None);
+            return;
+        }
+        if !self.is_representative(fill_cards) {
+            return;
+        }
+        let images = self
+            .symmetries
+            .iter()
+            .map(|(permutation, _)| permutation.cards(fill_cards))
+            .collect_vec();
+
+        self.record.clear();
+        self.evaluator
+            .evaluate(fill_cards, &mut self.scratch, Some(&mut self.record));
+        let outcomes = self.record.outcomes(0);
+        for (i, (_, hand_permutation)) in self.symmetries.iter().enumerate() {
+            if images[..i].contains(&images[i]) {
+                continue;
+            }
+            counts.runouts += 1;
+            counts.combination_counts[self.record.my_kind(0) as usize] += 1;
+            // candidate hand on this runout is permuted to the same result on the evaluated runout
+            for (&outcome, &hand_i) in outcomes.iter().zip(hand_permutation) {
+                outcome.count(&mut counts.hands[hand_i]);
+            }
+            if self.opponents > 1 {
+                for (count, runout_count) in counts
+                    .multiway_counts
+                    .iter_mut()
+                    .zip(self.record.multiway_counts(0, self.opponents))
+                {
+                    *count += runout_count;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::progress::ProgressReporter;
+    use crate::solve::{full_deck, solve_table_cards, solve_with_deck, table_cards};
+    use crate::types::{Rank, Table};
+    use futures::executor::block_on;
+    use rstest::rstest;
+
+    fn card(notation: &str) -> Card {
+        let chars = notation.chars().collect_vec();
+        Card {
+            rank: Rank::ALL["23456789TJQKA".find(chars[0]).unwrap()],
+            suit: Suit::ALL["hdsc".find(chars[1]).unwrap()],
+        }
+    }
+
+    fn cards(notation: &str) -> CardSet {
+        notation.split_whitespace().map(card).collect()
+    }
+
+    #[rstest]
+    #[case("Kc As", "Ah Kd")]
+    #[case("7s 7c", "7h 7d")]
+    #[case("Qs Js", "Qh Jh")]
+    fn canonical_hand_matches_precalculated_representative(
+        #[case] hand: &str,
+        #[case] expected: &str,
+    ) {
+        let permutation = canonical_permutation(cards(hand), CardSet::EMPTY, CardSet::EMPTY);
+        assert_eq!(permutation.cards(cards(hand)), cards(expected));
+    }
+
+    #[rstest]
+    fn equivalent_tables_have_same_canonical_form() {
+        let canonical = |hand: &str, board: &str, dead: &str| {
+            let (hand, board, dead) = (cards(hand), cards(board), cards(dead));
+            let permutation = canonical_permutation(hand, board, dead);
+            (
+                permutation.cards(hand),
+                permutation.cards(board),
+                permutation.cards(dead),
+            )
+        };
+
+        assert_eq!(
+            canonical("Ah Kh", "2s 7d 9c", "3c"),
+            canonical("As Ks", "2c 7h 9d", "3d"),
+        );
+        assert_ne!(
+            canonical("Ah Kh", "2s 7d 9c", "3c"),
+            canonical("As Ks", "2c 7h 9d", "3h"),
+        );
+    }
+
+    #[rstest]
+    fn inverse_permutation_restores_cards() {
+        let set = cards("Ah Kd 2s 7c 9c");
+        for permutation in SuitPermutation::all() {
+            assert_eq!(permutation.inverse().cards(permutation.cards(set)), set);
+        }
+    }
+
+    #[rstest]
+    // pre-flop on a reduced deck, with 3 interchangeable suits
+    #[case("Ah Kh", "", 2, None)]
+    #[case("Ah Kd", "", 3, None)]
+    #[case("Ah Kd", "", 2, Some("QQ+, AKs"))]
+    // spades and clubs are interchangeable
+    #[case("Ad Kd", "Qh Jh Th", 2, None)]
+    fn symmetric_solve_matches_full_enumeration(
+        #[case] hand: &str,
+        #[case] board: &str,
+        #[case] players: usize,
+        #[case] opponent_range: Option<&str>,
+    ) {
+        let deck = full_deck()
+            .iter()
+            .cloned()
+            .filter(|card| card.rank >= Rank::N10)
+            .collect_vec();
+        let mut board = board
+            .split_whitespace()
+            .map(|c| Some(card(c)))
+            .collect_vec();
+        board.resize(5, None);
+        let table = Table {
+            hand: hand.split_whitespace().map(card).collect(),
+            board: board.into(),
+            players,
+            opponent_range: opponent_range.map(|range| range.parse().unwrap()),
+            ..Default::default()
+        };
+        let solution = block_on(solve_with_deck(&table, &deck)).unwrap();
+        let table_cards = table_cards(&table, &deck).unwrap();
+        // runouts are evaluated one by one when recorded
+        let mut record = RunoutRecord::new(table_cards.hands.len());
+        let evaluated = block_on(solve_table_cards(
+            &table_cards,
+            Some(&mut record),
+            &mut ProgressReporter::new(None),
+        ));
+
+        assert_eq!(
+            serde_json::to_string(&solution).unwrap(),
+            serde_json::to_string(&evaluated).unwrap()
+        );
+    }
+}
@@ -1,11 +1,31 @@
+pub mod cache;
+pub mod decision;
+pub mod isomorphism;
+pub mod next_card;
+pub mod outs;
+#[cfg(feature = "parallel")]
+pub mod parallel;
+pub mod progress;
+pub mod range;
+pub mod sample;
+pub mod shard;
+pub mod showdown;
 pub mod signal;
 pub mod solve;
+pub mod solver;
 pub mod types;
 mod wasm_types;
 
-use crate::wasm_types::{from_wasm_table, to_wasm_solution};
-use anyhow::Error;
+use crate::wasm_types::{
+    from_wasm_board, from_wasm_table, from_wasm_variant, to_wasm_next_cards_solution,
+    to_wasm_outs_solution, to_wasm_progress, to_wasm_range_solution, to_wasm_showdown_solution,
+    to_wasm_solution,
+};
+use anyhow::{Context, Error};
 use solve as solve_inner;
+use std::cell::RefCell;
+use std::future::Future;
+use std::time::Duration;
 use wasm_bindgen::prelude::*;
 
 #[cfg(target_arch = "wasm32")]
@@ -57,7 +77,7 @@ mod platform {
         Ok(buf.into())
     }
 
-    pub static ROOT_PATH: &'static str = "";
+    pub static ROOT_PATH: &str = "";
 }
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -76,7 +96,7 @@ mod platform {
         Ok(buf.into())
     }
 
-    pub static ROOT_PATH: &'static str = "../";
+    pub static ROOT_PATH: &str = "../";
 }
 
 pub use platform::{log, read_file, ROOT_PATH};
@@ -113,13 +133,46 @@ impl MaybeCard {
 pub struct Table {
     pub hand: Box<[MaybeCard]>,
     pub board: Box<[MaybeCard]>,
+    pub players: usize,
+    // range notation, e.g. "QQ+, AKs, ATs-A8s"
+    pub opponent_range: Option<String>,
+    pub dead: Box<[MaybeCard]>,
+    // opponents' hands when known, each two consecutive cards are one hand
+    pub known_hands: Box<[MaybeCard]>,
+    pub variant: Variant,
+}
+
+#[wasm_bindgen]
+#[derive(Copy, Clone, Debug, Default)]
+pub enum Variant {
+    #[default]
+    Holdem,
+    ShortDeck,
+    // short deck where three of a kind beats a straight
+    ShortDeckTripsBeatStraight,
 }
 
 #[wasm_bindgen]
 impl Table {
     #[wasm_bindgen(constructor)]
-    pub fn new(hand: Box<[MaybeCard]>, board: Box<[MaybeCard]>) -> Table {
-        Table { hand, board }
+    pub fn new(
+        hand: Box<[MaybeCard]>,
+        board: Box<[MaybeCard]>,
+        players: usize,
+        opponent_range: Option<String>,
+        dead: Box<[MaybeCard]>,
+        known_hands: Box<[MaybeCard]>,
+        variant: Option<Variant>,
+    ) -> Table {
+        Table {
+            hand,
+            board,
+            players,
+            opponent_range,
+            dead,
+            known_hands,
+            variant: variant.unwrap_or_default(),
+        }
     }
 }
 
@@ -130,6 +183,47 @@ pub struct Solution {
     pub board_possibilities: u64,
     pub win_count: u64,
     pub lose_count: u64,
+    // against all opponents together with more than one opponent
+    pub win_probability: f64,
+    pub tie_probability: f64,
+    // expected share of the pot
+    pub equity: f64,
+    pub multiway: Option<MultiwaySolution>,
+    pub approximation: Option<Approximation>,
+    // from high card to straight flush, empty if not available
+    pub combination_probabilities: Box<[CombinationProbability]>,
+    // candidate hands beating me by their combination kind, weighted by the hand weight
+    pub beats_me_combinations: Box<[CombinationCount]>,
+}
+
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Clone, Debug)]
+pub struct CombinationProbability {
+    pub combination: String,
+    pub probability: f64,
+}
+
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Clone, Debug)]
+pub struct Approximation {
+    pub equity: f64,
+    pub equity_standard_error: f64,
+    // 95% confidence interval
+    pub equity_lower_bound: f64,
+    pub equity_upper_bound: f64,
+    pub multiway_equity_standard_error: Option<f64>,
+}
+
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Clone, Debug)]
+pub struct MultiwaySolution {
+    pub opponents: u64,
+    pub possibilities: u64,
+    pub win_count: u64,
+    pub tie_counts: Box<[u64]>,
+    pub tie_count: u64,
+    pub lose_count: u64,
+    pub equity: f64,
 }
 
 #[wasm_bindgen(getter_with_clone)]
@@ -138,20 +232,214 @@ pub struct HandSolution {
     pub hand: Box<[Card]>,
     pub beats_me_count: u64,
     pub is_beaten_count: u64,
+    pub tie_count: u64,
+    pub weight: f64,
+    // only kinds with non-zero count
+    pub beats_me_combinations: Box<[CombinationCount]>,
+}
+
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Clone, Debug)]
+pub struct CombinationCount {
+    pub combination: String,
+    pub count: f64,
+}
+
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Clone, Debug)]
+pub struct RangeSolution {
+    pub hands: Box<[RangeHandSolution]>,
+    pub my_equity: f64,
+    pub opponent_equity: f64,
+}
+
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Clone, Debug)]
+pub struct RangeHandSolution {
+    pub hand: Box<[Card]>,
+    pub weight: f64,
+    pub win_count: f64,
+    pub tie_count: f64,
+    pub lose_count: f64,
+    pub equity: f64,
+}
+
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Clone, Debug)]
+pub struct ShowdownSolution {
+    pub board_possibilities: u64,
+    pub players: Box<[PlayerShowdown]>,
+}
+
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Clone, Debug)]
+pub struct PlayerShowdown {
+    pub hand: Box<[Card]>,
+    pub win_count: u64,
+    pub tie_count: u64,
+    pub equity: f64,
+}
+
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Clone, Debug)]
+pub struct OutsSolution {
+    pub groups: Box<[OutsGroup]>,
+    pub outs: f64,
+    pub one_card_odds: f64,
+    pub two_card_odds: Option<f64>,
+}
+
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Clone, Debug)]
+pub struct OutsGroup {
+    // e.g. "flush", "two_pairs"
+    pub combination: String,
+    pub outs: Box<[Out]>,
+}
+
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Clone, Debug)]
+pub struct Out {
+    pub card: Card,
+    pub share: f64,
+}
+
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Clone, Debug)]
+pub struct NextCardsSolution {
+    pub equity: f64,
+    pub cards: Box<[NextCardSolution]>,
+}
+
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Clone, Debug)]
+pub struct NextCardSolution {
+    pub card: Card,
+    pub win_count: f64,
+    pub tie_count: f64,
+    pub lose_count: f64,
+    pub equity: f64,
+    // "improves", "neutral" or "kills"
+    pub effect: String,
+}
+
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Clone, Debug)]
+pub struct CallDecision {
+    pub required_equity: f64,
+    pub call_ev: f64,
+    pub break_even_bet: Option<f64>,
+}
+
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Clone, Debug)]
+pub struct Progress {
+    pub fraction: f64,
+    pub elapsed_ms: f64,
+    pub remaining_ms: Option<f64>,
+}
+
+// calls the JS function with Progress, errors thrown by the function are ignored
+fn js_progress_sink(f: js_sys::Function) -> impl FnMut(progress::Progress) {
+    move |p| {
+        let _ = f.call1(&JsValue::NULL, &JsValue::from(to_wasm_progress(p)));
+    }
+}
+
+// calls the JS function with a partial Solution, errors thrown by the function are ignored
+fn js_snapshot_sink(f: js_sys::Function) -> impl FnMut(types::Solution) {
+    move |solution| {
+        let _ = f.call1(&JsValue::NULL, &JsValue::from(to_wasm_solution(&solution)));
+    }
+}
+
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Clone, Debug)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+    pub size_bytes: usize,
+}
+
+thread_local! {
+    // solutions of recently solved tables, shared by all solves
+    static SOLUTION_CACHE: RefCell<cache::SolutionCache> = Default::default();
+}
+
+// the future is not polled when the solution is cached, approximate solutions aren't cached so
+// that a repeated solve isn't answered with a sampled solution
+async fn solve_cached(
+    table: &types::Table,
+    solve: impl Future<Output = anyhow::Result<types::Solution>>,
+) -> anyhow::Result<types::Solution> {
+    if let Some(solution) = SOLUTION_CACHE.with_borrow_mut(|cache| cache.get(table)) {
+        return Ok(solution);
+    }
+    let solution = solve.await?;
+    if solution.approximation.is_none() {
+        SOLUTION_CACHE.with_borrow_mut(|cache| cache.insert(table, &solution));
+    }
+    Ok(solution)
+}
+
+#[wasm_bindgen]
+pub fn solution_cache_stats() -> CacheStats {
+    let stats = SOLUTION_CACHE.with_borrow(|cache| cache.stats());
+    CacheStats {
+        hits: stats.hits,
+        misses: stats.misses,
+        entries: stats.entries,
+        size_bytes: stats.size_bytes,
+    }
+}
+
+// pre-flop tables without a precalculated solution and large multiway tables are sampled,
+// enumerating them is too slow
+fn sampling(samples_per_hand: Option<u32>) -> sample::SamplingConfig {
+    let default = sample::SamplingConfig::default();
+    sample::SamplingConfig {
+        samples_per_hand: samples_per_hand.map_or(default.samples_per_hand, u64::from),
+        ..default
+    }
 }
 
 #[wasm_bindgen]
 pub async fn solve(
     cancellation_token: &signal::AbortSignal,
     t: &Table,
+    progress: Option<js_sys::Function>,
+    snapshot: Option<js_sys::Function>,
+    samples_per_hand: Option<u32>,
 ) -> Result<Solution, String> {
+    let mut progress_sink = progress.map(js_progress_sink);
+    let mut snapshot_sink = snapshot.map(js_snapshot_sink);
     let mut drop_detector = signal::DropDetector {
         s: "parsing",
         f: |s| log(format!("dropped {}", s).as_str()),
     };
     let table = to_str_err(from_wasm_table(t))?;
     drop_detector.s = "pending";
-    let solution_result = solve_inner::solve(cancellation_token.clone(), &table).await;
+    let mut reporter = progress::ProgressReporter::new(
+        progress_sink
+            .as_mut()
+            .map(|sink| sink as progress::ProgressSink),
+    )
+    .with_snapshots(
+        snapshot_sink
+            .as_mut()
+            .map(|sink| sink as progress::SnapshotSink),
+    );
+    let solution_result = solve_cached(
+        &table,
+        solve_inner::solve_with_progress(
+            cancellation_token.clone(),
+            &table,
+            Some(&sampling(samples_per_hand)),
+            &mut reporter,
+        ),
+    )
+    .await;
     drop_detector.s = "error result";
     let solution = to_str_err(solution_result)?;
     let result = Ok(to_wasm_solution(&solution));
@@ -159,6 +447,249 @@ pub async fn solve(
     result
 }
 
+// Keeps the runouts of the last solved table, so that solving it again with one more board card
+// is fast. Solves running at the same time don't share the runouts.
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct Solver {
+    inner: RefCell<solver::Solver>,
+}
+
+#[wasm_bindgen]
+impl Solver {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Solver {
+        Default::default()
+    }
+
+    pub async fn solve(
+        &self,
+        cancellation_token: &signal::AbortSignal,
+        t: &Table,
+        progress: Option<js_sys::Function>,
+        snapshot: Option<js_sys::Function>,
+        samples_per_hand: Option<u32>,
+    ) -> Result<Solution, String> {
+        let table = to_str_err(from_wasm_table(t))?;
+        let mut progress_sink = progress.map(js_progress_sink);
+        let mut snapshot_sink = snapshot.map(js_snapshot_sink);
+        let mut reporter = progress::ProgressReporter::new(
+            progress_sink
+                .as_mut()
+                .map(|sink| sink as progress::ProgressSink),
+        )
+        .with_snapshots(
+            snapshot_sink
+                .as_mut()
+                .map(|sink| sink as progress::SnapshotSink),
+        );
+        let mut inner = self.inner.take();
+        // runouts of the last table are reused before looking into the cache, so that the solver
+        // keeps the runouts when the next board card is added
+        let solution_result = match inner.solve_recorded(&table, &solve_inner::deck(table.variant))
+        {
+            Some(solution) => Ok(solution),
+            None => {
+                solve_cached(
+                    &table,
+                    solve_inner::solve_incremental(
+                        cancellation_token.clone(),
+                        &mut inner,
+                        &table,
+                        Some(&sampling(samples_per_hand)),
+                        &mut reporter,
+                    ),
+                )
+                .await
+            }
+        };
+        self.inner.replace(inner);
+        let solution = to_str_err(solution_result)?;
+        Ok(to_wasm_solution(&solution))
+    }
+}
+
+// Solves exactly if it's projected to take at most the time budget, otherwise the solution is
+// sampled and has an approximation.
+#[wasm_bindgen]
+pub async fn solve_with_time_budget(
+    cancellation_token: &signal::AbortSignal,
+    t: &Table,
+    time_budget_ms: u32,
+    progress: Option<js_sys::Function>,
+) -> Result<Solution, String> {
+    let table = to_str_err(from_wasm_table(t))?;
+    let mut progress_sink = progress.map(js_progress_sink);
+    let mut reporter = progress::ProgressReporter::new(
+        progress_sink
+            .as_mut()
+            .map(|sink| sink as progress::ProgressSink),
+    );
+    let solution = to_str_err(
+        solve_cached(
+            &table,
+            solve_inner::solve_with_time_budget(
+                cancellation_token.clone(),
+                &table,
+                Duration::from_millis(time_budget_ms.into()),
+                &mut reporter,
+            ),
+        )
+        .await,
+    )?;
+    Ok(to_wasm_solution(&solution))
+}
+
+#[wasm_bindgen]
+pub async fn solve_sampled(
+    cancellation_token: &signal::AbortSignal,
+    t: &Table,
+    samples_per_hand: u64,
+    time_budget_ms: Option<u32>,
+    seed: u64,
+) -> Result<Solution, String> {
+    let table = to_str_err(from_wasm_table(t))?;
+    let config = sample::SamplingConfig {
+        samples_per_hand,
+        time_budget: time_budget_ms.map(|ms| Duration::from_millis(ms.into())),
+        seed,
+    };
+    let solution = to_str_err(
+        solve_inner::solve_sampled_table(cancellation_token.clone(), &table, &config).await,
+    )?;
+    Ok(to_wasm_solution(&solution))
+}
+
+#[wasm_bindgen]
+pub async fn solve_next_cards(
+    cancellation_token: &signal::AbortSignal,
+    t: &Table,
+) -> Result<NextCardsSolution, String> {
+    let table = to_str_err(from_wasm_table(t))?;
+    let solution =
+        to_str_err(solve_inner::solve_next_cards(cancellation_token.clone(), &table).await)?;
+    Ok(to_wasm_next_cards_solution(&solution))
+}
+
+#[wasm_bindgen]
+pub async fn solve_showdown(
+    cancellation_token: &signal::AbortSignal,
+    t: &Table,
+) -> Result<ShowdownSolution, String> {
+    let table = to_str_err(from_wasm_table(t))?;
+    let solution =
+        to_str_err(solve_inner::solve_showdown(cancellation_token.clone(), &table).await)?;
+    Ok(to_wasm_showdown_solution(&solution))
+}
+
+// Counts of a share of the runouts, serialized to be sent from a web worker to the main thread.
+#[wasm_bindgen]
+pub async fn solve_shard(
+    cancellation_token: &signal::AbortSignal,
+    t: &Table,
+    shard: u32,
+    shards: u32,
+) -> Result<String, String> {
+    let table = to_str_err(from_wasm_table(t))?;
+    let counts = to_str_err(
+        solve_inner::solve_shard(
+            cancellation_token.clone(),
+            &table,
+            shard as usize,
+            shards as usize,
+        )
+        .await,
+    )?;
+    to_str_err(serde_json::to_string(&counts).map_err(Error::from))
+}
+
+// Solution of the table from the solution cache, e.g. before the table is split into shards.
+#[wasm_bindgen]
+pub fn cached_solution(t: &Table) -> Result<Option<Solution>, String> {
+    let table = to_str_err(from_wasm_table(t))?;
+    let solution = SOLUTION_CACHE.with_borrow_mut(|cache| cache.get(&table));
+    Ok(solution.as_ref().map(to_wasm_solution))
+}
+
+// Merged solution is cached like the solution of `solve`.
+#[wasm_bindgen]
+pub fn merge_shards(t: &Table, shards: Vec<String>) -> Result<Solution, String> {
+    let table = to_str_err(from_wasm_table(t))?;
+    let shards = to_str_err(
+        shards
+            .iter()
+            .map(|shard| serde_json::from_str(shard))
+            .collect::<Result<Vec<_>, _>>()
+            .context("invalid shard counts"),
+    )?;
+    let solution = to_str_err(shard::merge_shards_with_deck(
+        &table,
+        &solve_inner::deck(table.variant),
+        shards,
+    ))?;
+    SOLUTION_CACHE.with_borrow_mut(|cache| cache.insert(&table, &solution));
+    Ok(to_wasm_solution(&solution))
+}
+
+#[wasm_bindgen]
+pub fn outs(t: &Table) -> Result<OutsSolution, String> {
+    let table = to_str_err(from_wasm_table(t))?;
+    let solution = to_str_err(outs::outs_with_deck(
+        &table,
+        &solve_inner::deck(table.variant),
+    ))?;
+    Ok(to_wasm_outs_solution(&solution))
+}
+
+// pot includes opponent's bet that is to be called
+#[wasm_bindgen]
+pub fn call_decision(
+    solution: &Solution,
+    pot: f64,
+    to_call: f64,
+    rake_percent: Option<f64>,
+) -> Result<CallDecision, String> {
+    let situation = decision::CallSituation {
+        pot,
+        to_call,
+        rake_percent: rake_percent.unwrap_or(0.0),
+    };
+    let decision = to_str_err(decision::call_decision_with_equity(
+        solution.equity,
+        &situation,
+    ))?;
+    Ok(CallDecision {
+        required_equity: decision.required_equity,
+        call_ev: decision.call_ev,
+        break_even_bet: decision.break_even_bet,
+    })
+}
+
+#[wasm_bindgen]
+pub async fn solve_ranges(
+    cancellation_token: &signal::AbortSignal,
+    my_range: String,
+    opponent_range: String,
+    board: Box<[MaybeCard]>,
+    variant: Option<Variant>,
+) -> Result<RangeSolution, String> {
+    let my_range: range::Range = to_str_err(my_range.parse().context("invalid my range"))?;
+    let opponent_range: range::Range =
+        to_str_err(opponent_range.parse().context("invalid opponent range"))?;
+    let board = to_str_err(from_wasm_board(&board))?;
+    let solution = to_str_err(
+        solve_inner::solve_ranges(
+            cancellation_token.clone(),
+            &my_range,
+            &opponent_range,
+            &board,
+            from_wasm_variant(variant.unwrap_or_default()),
+        )
+        .await,
+    )?;
+    Ok(to_wasm_range_solution(&solution))
+}
+
 fn to_str_err<T>(v: Result<T, Error>) -> Result<T, String> {
     v.map_err(|e| format!("{:#}", e))
 }
@@ -0,0 +1,224 @@
+use crate::progress::ProgressReporter;
+use crate::solve::{solve_table_cards, table_cards};
+use crate::solver::{solution_from_record, RunoutRecord, MAX_RECORDED_CHOOSE};
+use crate::types::{Card, NextCardEffect, NextCardSolution, NextCardsSolution, Table};
+use anyhow::{bail, Result};
+use itertools::Itertools;
+
+// next cards changing my equity by less than this are neutral
+pub const NEUTRAL_EQUITY_MARGIN: f64 = 0.02;
+
+/// Solves the table for each card that can be dealt into the first empty board slot, and
+/// classifies the cards by how they change my equity. All runouts are evaluated once, and each
+/// card is solved from the recorded runouts containing it, so only the flop and the turn are
+/// supported.
+pub async fn solve_next_cards_with_deck(table: &Table, deck: &[Card]) -> Result<NextCardsSolution> {
+    let Some(next_slot) = table.board.iter().position(Option::is_none) else {
+        bail!("board is already complete, there is no next card")
+    };
+    let current = table_cards(table, deck)?;
+    if current.choose > MAX_RECORDED_CHOOSE {
+        bail!("next cards can be solved only on the flop or the turn")
+    }
+    let mut record = RunoutRecord::new(current.hands.len());
+    solve_table_cards(
+        &current,
+        Some(&mut record),
+        &mut ProgressReporter::new(None),
+    )
+    .await;
+
+    let mut cards = Vec::with_capacity(current.remaining_deck.len());
+    for &card in &current.remaining_deck {
+        let mut next_table = table.clone();
+        let mut board = next_table.board.to_vec();
+        board[next_slot] = Some(card);
+        next_table.board = board.into();
+        let next = table_cards(&next_table, deck)?;
+        // candidate hands keep their order when a card is removed from the deck
+        let columns = current
+            .hands
+            .iter()
+            .positions(|hand| !hand.hand.contains(&card))
+            .collect_vec();
+        let solution = solution_from_record(&next, &record.with_card(card, &columns));
+        let (win_count, tie_count, lose_count) = solution.outcome_counts();
+        cards.push(NextCardSolution {
+            card,
+            win_count,
+            tie_count,
+            lose_count,
+            equity: solution.equity(),
+            effect: NextCardEffect::Neutral,
+        });
+    }
+
+    // every completed board is counted once for each of its cards being the next one, so the
+    // totals over all next cards give the current equity
+    let (equity_sum, total) = cards.iter().fold((0.0, 0.0), |(equity_sum, total), c| {
+        let count = c.win_count + c.tie_count + c.lose_count;
+        (equity_sum + c.equity * count, total + count)
+    });
+    let equity = equity_sum / total;
+    for card in &mut cards {
+        card.effect = if card.equity > equity + NEUTRAL_EQUITY_MARGIN {
+            NextCardEffect::Improves
+        } else if card.equity < equity - NEUTRAL_EQUITY_MARGIN {
+            NextCardEffect::Kills
+        } else {
+            NextCardEffect::Neutral
+        };
+    }
+    cards.sort_by(|a, b| b.equity.total_cmp(&a.equity).then(a.card.cmp(&b.card)));
+
+    Ok(NextCardsSolution {
+        equity,
+        cards: cards.into(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solve::{full_deck, solve_with_deck};
+    use futures::executor::block_on;
+    use rstest::rstest;
+
+    #[rstest]
+    fn next_cards_average_to_current_equity() {
+        let deck = full_deck();
+        let table = Table {
+            hand: vec![deck[12], deck[11]].into_boxed_slice(),
+            board: vec![
+                Some(deck[10]),
+                Some(deck[22]),
+                Some(deck[3]),
+                Some(deck[40]),
+                None,
+            ]
+            .into_boxed_slice(),
+            ..Default::default()
+        };
+        let result = block_on(solve_next_cards_with_deck(&table, &deck)).unwrap();
+        let current = block_on(solve_with_deck(&table, &deck)).unwrap();
+
+        assert_eq!(result.cards.len(), 52 - 6);
+        assert!((result.equity - current.equity()).abs() < 1e-12);
+        assert!(result.cards.windows(2).all(|w| w[0].equity >= w[1].equity));
+        for card in result.cards.iter() {
+            let expected_effect = if card.equity > result.equity + NEUTRAL_EQUITY_MARGIN {
+                NextCardEffect::Improves
+            } else if card.equity < result.equity - NEUTRAL_EQUITY_MARGIN {
+                NextCardEffect::Kills
+            } else {
+                NextCardEffect::Neutral
+            };
+            assert_eq!(card.effect, expected_effect);
+        }
+    }
+
+    #[rstest]
+    fn flush_card_improves_flush_draw() {
+        let deck = full_deck();
+        // deck is ordered by suit, so cards 0..13 share a suit
+        let table = Table {
+            hand: vec![deck[12], deck[7]].into_boxed_slice(),
+            board: vec![
+                Some(deck[2]),
+                Some(deck[4]),
+                Some(deck[14]),
+                Some(deck[45]),
+                None,
+            ]
+            .into_boxed_slice(),
+            ..Default::default()
+        };
+        let result = block_on(solve_next_cards_with_deck(&table, &deck)).unwrap();
+        // nut flush, no straight flush or full house possible
+        let flush_card = result.cards.iter().find(|c| c.card == deck[10]).unwrap();
+
+        assert_eq!(flush_card.effect, NextCardEffect::Improves);
+        assert_eq!(flush_card.lose_count, 0.0);
+    }
+
+    #[rstest]
+    fn multiway_next_cards_average_to_current_equity() {
+        let deck = full_deck();
+        let table = Table {
+            hand: vec![deck[12], deck[11]].into_boxed_slice(),
+            board: vec![
+                Some(deck[10]),
+                Some(deck[22]),
+                Some(deck[3]),
+                Some(deck[40]),
+                None,
+            ]
+            .into_boxed_slice(),
+            players: 3,
+            ..Default::default()
+        };
+        let result = block_on(solve_next_cards_with_deck(&table, &deck)).unwrap();
+        let current = block_on(solve_with_deck(&table, &deck)).unwrap();
+
+        assert!((result.equity - current.equity()).abs() < 1e-12);
+    }
+
+    #[rstest]
+    fn next_cards_match_solving_each_next_table() {
+        let deck = full_deck();
+        let table = Table {
+            hand: vec![deck[12], deck[11]].into_boxed_slice(),
+            board: vec![Some(deck[10]), Some(deck[22]), Some(deck[3]), None, None]
+                .into_boxed_slice(),
+            opponent_range: Some("22+, A2s+, K9o+, 76s".parse().unwrap()),
+            ..Default::default()
+        };
+        let result = block_on(solve_next_cards_with_deck(&table, &deck)).unwrap();
+
+        assert_eq!(result.cards.len(), 52 - 5);
+        for card in result.cards.iter() {
+            let mut board = table.board.to_vec();
+            board[3] = Some(card.card);
+            let next_table = Table {
+                board: board.into(),
+                ..table.clone()
+            };
+            let expected = block_on(solve_with_deck(&next_table, &deck)).unwrap();
+            assert_eq!(
+                (card.win_count, card.tie_count, card.lose_count),
+                expected.outcome_counts()
+            );
+        }
+    }
+
+    #[rstest]
+    #[case(vec![None; 5])]
+    #[case(vec![Some(10), Some(22), None, None, None])]
+    fn before_flop_next_cards_are_not_solved(#[case] board: Vec<Option<usize>>) {
+        let deck = full_deck();
+        let table = Table {
+            hand: vec![deck[12], deck[11]].into_boxed_slice(),
+            board: board.into_iter().map(|i| i.map(|i| deck[i])).collect(),
+            ..Default::default()
+        };
+        assert!(block_on(solve_next_cards_with_deck(&table, &deck)).is_err());
+    }
+
+    #[rstest]
+    fn complete_board_has_no_next_card() {
+        let deck = full_deck();
+        let table = Table {
+            hand: vec![deck[12], deck[11]].into_boxed_slice(),
+            board: vec![
+                Some(deck[10]),
+                Some(deck[22]),
+                Some(deck[3]),
+                Some(deck[40]),
+                Some(deck[41]),
+            ]
+            .into_boxed_slice(),
+            ..Default::default()
+        };
+        assert!(block_on(solve_next_cards_with_deck(&table, &deck)).is_err());
+    }
+}
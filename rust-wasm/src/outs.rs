@@ -0,0 +1,225 @@
+use crate::solve::{
+    best_combination, best_combination_from_set, n_choose_m, table_cards, TableCards,
+};
+use crate::types::{Card, CardSet, CombinationKind, Out, OutsGroup, OutsSolution, Table};
+use anyhow::{bail, Result};
+use itertools::Itertools;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+/// Outs against opponent's hand or range: next cards after which I win or tie against opponent's
+/// hands that are currently ahead of me. Odds are exact, conditioned on opponent holding one of the
+/// hands ahead of me (with probability by their weight in the range).
+pub fn outs_with_deck(table: &Table, deck: &[Card]) -> Result<OutsSolution> {
+    if table.opponent_range.is_none() {
+        bail!("outs are calculated only against opponent's hand or range")
+    }
+    let TableCards {
+        my_cards,
+        fixed_board_cards,
+        remaining_deck,
+        choose,
+        hands,
+        variant,
+        ..
+    } = table_cards(table, deck)?;
+    if !(1..=2).contains(&choose) {
+        bail!("outs are calculated only on the flop or turn")
+    }
+
+    let my_combination = best_combination(fixed_board_cards | my_cards, variant);
+    let ahead_hands = hands
+        .iter()
+        .map(|hand| (hand.hand.iter().collect::<CardSet>(), hand.weight))
+        .filter(|&(cards, _)| best_combination(fixed_board_cards | cards, variant) > my_combination)
+        .collect_vec();
+    let total_weight: f64 = ahead_hands.iter().map(|&(_, weight)| weight).sum();
+    if total_weight == 0.0 {
+        bail!("I'm not behind opponent's hand or range")
+    }
+
+    // weight of opponent's hands ahead of me that each card is an out against
+    let mut card_weights = vec![0.0; remaining_deck.len()];
+    let (mut one_card_odds, mut two_card_odds) = (0.0, 0.0);
+    for &(cards, weight) in &ahead_hands {
+        let mut outs_count = 0;
+        for (card_weight, &card) in card_weights.iter_mut().zip(&remaining_deck) {
+            if cards.contains(card) {
+                continue;
+            }
+            let board_cards = fixed_board_cards | card.into();
+            if best_combination(board_cards | my_cards, variant)
+                >= best_combination(board_cards | cards, variant)
+            {
+                *card_weight += weight;
+                outs_count += 1;
+            }
+        }
+        let unseen_count = remaining_deck.len() - cards.len();
+        one_card_odds += weight * outs_count as f64 / unseen_count as f64;
+        two_card_odds += weight
+            * (1.0
+                - n_choose_m(unseen_count - outs_count, 2) as f64
+                    / n_choose_m(unseen_count, 2) as f64);
+    }
+
+    let mut groups: HashMap<CombinationKind, Vec<Out>> = HashMap::new();
+    for (&card, &card_weight) in remaining_deck.iter().zip(&card_weights) {
+        if card_weight == 0.0 {
+            continue;
+        }
+        let combination =
+            best_combination_from_set(fixed_board_cards | my_cards | card.into(), variant);
+        groups.entry(combination.kind()).or_default().push(Out {
+            card,
+            share: card_weight / total_weight,
+        });
+    }
+    let groups = groups
+        .into_iter()
+        .sorted_by_key(|&(combination, _)| Reverse(variant.kind_strength(combination)))
+        .map(|(combination, mut outs)| {
+            outs.sort_by(|a, b| b.share.total_cmp(&a.share).then(a.card.cmp(&b.card)));
+            OutsGroup {
+                combination,
+                outs: outs.into(),
+            }
+        })
+        .collect_vec();
+
+    Ok(OutsSolution {
+        outs: groups
+            .iter()
+            .flat_map(|group| group.outs.iter())
+            .map(|out| out.share)
+            .sum(),
+        groups: groups.into(),
+        one_card_odds: one_card_odds / total_weight,
+        two_card_odds: (choose == 2).then_some(two_card_odds / total_weight),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solve::{deck, full_deck};
+    use crate::types::{Rank, Suit, Variant};
+    use rstest::rstest;
+
+    const SHORT_DECK: Variant = Variant::ShortDeck {
+        trips_beat_straight: false,
+    };
+
+    fn card(notation: &str) -> Card {
+        let chars = notation.chars().collect_vec();
+        Card {
+            rank: Rank::ALL["23456789TJQKA".find(chars[0]).unwrap()],
+            suit: Suit::ALL["hdsc".find(chars[1]).unwrap()],
+        }
+    }
+
+    fn table(hand: &[&str], board: &[&str], opponent_range: &str) -> Table {
+        let mut board = board.iter().map(|c| Some(card(c))).collect_vec();
+        board.resize(5, None);
+        Table {
+            hand: hand.iter().map(|c| card(c)).collect(),
+            board: board.into(),
+            opponent_range: Some(opponent_range.parse().unwrap()),
+            ..Default::default()
+        }
+    }
+
+    #[rstest]
+    fn flush_draw_against_set() {
+        let deck = full_deck();
+        // hearts pairing the board give opponent full house or quads
+        let table = table(&["Ah", "Kh"], &["2h", "7h", "9c"], "9s9d");
+        let result = outs_with_deck(&table, &deck).unwrap();
+
+        assert_eq!(result.groups.len(), 1);
+        assert_eq!(result.groups[0].combination, CombinationKind::Flush);
+        assert_eq!(result.groups[0].outs.len(), 8);
+        assert!(result.groups[0].outs.iter().all(|out| out.share == 1.0));
+        assert_eq!(result.outs, 8.0);
+        assert_eq!(result.one_card_odds, 8.0 / 45.0);
+        assert_eq!(
+            result.two_card_odds,
+            Some(1.0 - (37.0 * 36.0) / (45.0 * 44.0))
+        );
+    }
+
+    #[rstest]
+    fn flush_draw_against_range_on_turn() {
+        let deck = full_deck();
+        // 9h and Th give full house to the set, but not to the straight
+        let table = table(&["Ah", "Kh"], &["2h", "7h", "9c", "Td"], "9s9d, Jc8c");
+        let result = outs_with_deck(&table, &deck).unwrap();
+        let outs = &result.groups[0].outs;
+
+        assert_eq!(result.groups.len(), 1);
+        assert_eq!(outs.len(), 9);
+        assert!(outs[..7].iter().all(|out| out.share == 1.0));
+        assert_eq!(outs[7].card, card("9h"));
+        assert_eq!(outs[7].share, 0.5);
+        assert_eq!(outs[8].card, card("Th"));
+        assert_eq!(outs[8].share, 0.5);
+        assert_eq!(result.outs, 8.0);
+        assert!((result.one_card_odds - 8.0 / 44.0).abs() < 1e-12);
+        assert_eq!(result.two_card_odds, None);
+    }
+
+    #[rstest]
+    #[case(Variant::Holdem, vec![CombinationKind::FullHouse, CombinationKind::Flush])]
+    #[case(SHORT_DECK, vec![CombinationKind::Flush, CombinationKind::FullHouse])]
+    fn outs_groups_follow_variant_order(
+        #[case] variant: Variant,
+        #[case] expected: Vec<CombinationKind>,
+    ) {
+        // two pair with a flush draw against a set, pairing the board gives opponent full house
+        let table = Table {
+            variant,
+            ..table(&["Ah", "Kh"], &["7h", "8h", "As", "Kd"], "7c7d")
+        };
+        let result = outs_with_deck(&table, &deck(variant)).unwrap();
+
+        let groups = result
+            .groups
+            .iter()
+            .map(|group| group.combination)
+            .collect_vec();
+        assert_eq!(groups, expected);
+    }
+
+    #[rstest]
+    fn short_deck_flush_beats_full_house() {
+        // opponent already has a full house, only short deck flush beats it
+        let holdem = table(&["Ah", "Qh"], &["7h", "8h", "8s", "Kd"], "7c7d");
+        let short_deck = Table {
+            variant: SHORT_DECK,
+            ..holdem.clone()
+        };
+        assert!(outs_with_deck(&holdem, &full_deck())
+            .unwrap()
+            .groups
+            .is_empty());
+        let result = outs_with_deck(&short_deck, &deck(SHORT_DECK)).unwrap();
+
+        assert_eq!(result.groups.len(), 1);
+        assert_eq!(result.groups[0].combination, CombinationKind::Flush);
+        assert_eq!(
+            result.groups[0]
+                .outs
+                .iter()
+                .map(|out| out.card)
+                .collect_vec(),
+            ["6h", "9h", "Th", "Jh", "Kh"].map(card)
+        );
+    }
+
+    #[rstest]
+    #[case(table(&["Ah", "Ad"], &["2h", "7h", "9c"], "KsKd"))]
+    #[case(table(&["Ah", "Kh"], &["2h", "7h", "9c", "Td", "3s"], "9s9d"))]
+    fn rejects_table_without_outs(#[case] table: Table) {
+        assert!(outs_with_deck(&table, &full_deck()).is_err());
+    }
+}
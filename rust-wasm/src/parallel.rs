@@ -0,0 +1,79 @@
+use crate::isomorphism::SymmetricEvaluator;
+use crate::solve::{next_combination, table_cards, RunoutCounts};
+use crate::types::{Card, CardSet, Solution, Table};
+use anyhow::Result;
+use itertools::Itertools;
+use rayon::prelude::*;
+
+// runouts are split into tasks by their first cards
+const SPLIT_CARDS: usize = 2;
+
+/// Same as `solve_with_deck`, but evaluates the runouts on all threads of the rayon pool.
+pub fn solve_with_deck_parallel(table: &Table, deck: &[Card]) -> Result<Solution> {
+    let table_cards = table_cards(table, deck)?;
+    let remaining_deck = &table_cards.remaining_deck;
+    let choose = table_cards.choose;
+    let split = choose.min(SPLIT_CARDS);
+    let rest = choose - split;
+
+    let counts = (0..remaining_deck.len())
+        .combinations(split)
+        // the rest of the runout is taken from cards after the split ones
+        .filter(|first| first.last().map_or(0, |&i| i + 1) + rest <= remaining_deck.len())
+        .collect_vec()
+        .into_par_iter()
+        .map(|first| {
+            let mut evaluator = SymmetricEvaluator::new(&table_cards);
+            let mut counts = RunoutCounts::new(&table_cards);
+            let first_cards: CardSet = first.iter().map(|&i| remaining_deck[i]).collect();
+            let rest_deck = &remaining_deck[first.last().map_or(0, |&i| i + 1)..];
+            let mut rest_map_i = Vec::from_iter(0..rest);
+            loop {
+                let fill_cards = first_cards | rest_map_i.iter().map(|&i| rest_deck[i]).collect();
+                evaluator.evaluate(fill_cards, &mut counts);
+
+                if !next_combination(&mut rest_map_i, rest_deck.len()) {
+                    break;
+                }
+            }
+            counts
+        })
+        .reduce(|| RunoutCounts::new(&table_cards), RunoutCounts::merge);
+
+    Ok(counts.into_solution(&table_cards))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solve::{full_deck, solve_with_deck};
+    use futures::executor::block_on;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(vec![Some(10), Some(22), Some(3), None, None], 2, None)]
+    #[case(vec![Some(10), Some(22), Some(3), Some(40), None], 3, None)]
+    #[case(vec![Some(10), Some(22), Some(3), Some(40), Some(7)], 4, None)]
+    #[case(vec![Some(10), Some(22), Some(3), None, None], 2, Some("22+, A2s+, K9o+"))]
+    fn matches_single_threaded_solve(
+        #[case] board: Vec<Option<usize>>,
+        #[case] players: usize,
+        #[case] opponent_range: Option<&str>,
+    ) {
+        let deck = full_deck();
+        let table = Table {
+            hand: vec![deck[12], deck[11]].into_boxed_slice(),
+            board: board.into_iter().map(|i| i.map(|i| deck[i])).collect(),
+            players,
+            opponent_range: opponent_range.map(|range| range.parse().unwrap()),
+            ..Default::default()
+        };
+        let expected = block_on(solve_with_deck(&table, &deck)).unwrap();
+        let solution = solve_with_deck_parallel(&table, &deck).unwrap();
+
+        assert_eq!(
+            serde_json::to_string(&solution).unwrap(),
+            serde_json::to_string(&expected).unwrap()
+        );
+    }
+}
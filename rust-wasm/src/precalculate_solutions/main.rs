@@ -1,18 +1,64 @@
 use std::fs::File;
 use strum::IntoEnumIterator;
-use rust_wasm::types::{PrecalculatedSolution, Card, Rank, Suit, Table};
-use anyhow::Result;
-use async_std::task::block_on;
+use rust_wasm::types::{PrecalculatedSolution, Card, Rank, Suit, Table, Solution, Variant};
+use anyhow::{bail, Result};
 use serde::{Serialize};
-use rust_wasm::solve::{full_deck, solve_with_deck};
+use rust_wasm::solve::{deck, precalculated_solutions_file};
+
+#[cfg(feature = "parallel")]
+fn solve_hand(table: &Table, deck: &[Card]) -> Result<Solution> {
+    rust_wasm::parallel::solve_with_deck_parallel(table, deck)
+}
+
+#[cfg(not(feature = "parallel"))]
+fn solve_hand(table: &Table, deck: &[Card]) -> Result<Solution> {
+    use rust_wasm::progress::{Progress, ProgressReporter};
+    // prints a line at each 10%
+    let mut printed_tenths = 0;
+    let mut print_eta = |progress: Progress| {
+        let tenths = (progress.fraction * 10.0) as u32;
+        if tenths <= printed_tenths || tenths >= 10 {
+            return;
+        }
+        printed_tenths = tenths;
+        if let Some(remaining) = progress.remaining {
+            println!("  {}%, {:.0}s remaining", tenths * 10, remaining.as_secs_f64());
+        }
+    };
+    let mut reporter = ProgressReporter::new(Some(&mut print_eta));
+    async_std::task::block_on(rust_wasm::solve::solve_with_deck_progress(
+        table,
+        deck,
+        &mut reporter,
+    ))
+}
+
+// hold'em by default, `--short-deck` (optionally with `--trips-beat-straight`) for short deck
+fn variant() -> Result<Variant> {
+    let mut short_deck = false;
+    let mut trips_beat_straight = false;
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--short-deck" => short_deck = true,
+            "--trips-beat-straight" => trips_beat_straight = true,
+            _ => bail!("unknown argument {}", arg),
+        }
+    }
+    Ok(match (short_deck, trips_beat_straight) {
+        (false, false) => Variant::Holdem,
+        (false, true) => bail!("--trips-beat-straight is a short deck rule"),
+        (true, trips_beat_straight) => Variant::ShortDeck { trips_beat_straight },
+    })
+}
 
 fn main() -> Result<()> {
-    let deck = full_deck();
+    let variant = variant()?;
+    let deck = deck(variant);
 
     let mut hand_representatives = vec![];
 
-    for rank1 in Rank::iter() {
-        for rank2 in Rank::iter() {
+    for rank1 in Rank::iter().filter(|&rank| rank >= variant.lowest_rank()) {
+        for rank2 in Rank::iter().filter(|&rank| rank >= variant.lowest_rank()) {
             if rank2 > rank1 {
                 continue;
             }
@@ -47,8 +93,6 @@ fn main() -> Result<()> {
         }
     }
 
-    let mut cache = Default::default();
-
     let len = hand_representatives.len();
     let mut precalculated_solutions = Vec::with_capacity(len);
     for (i, hand_representative) in hand_representatives.into_iter().enumerate() {
@@ -56,8 +100,10 @@ fn main() -> Result<()> {
         let table = Table {
             hand: hand_representative.clone(),
             board: vec![None, None, None, None, None].into_boxed_slice(),
+            variant,
+            ..Default::default()
         };
-        let hand_solution = block_on(solve_with_deck(&table, &deck, &mut cache))?;
+        let hand_solution = solve_hand(&table, &deck)?;
 
         precalculated_solutions.push(PrecalculatedSolution {
             my_hand: hand_representative,
@@ -65,7 +111,7 @@ fn main() -> Result<()> {
         });
     }
 
-    let file = File::create("../precalculated/preflop_solutions.json")?;
+    let file = File::create(format!("../precalculated/{}", precalculated_solutions_file(variant)))?;
     let mut serializer = serde_json::Serializer::new(&file);
     precalculated_solutions.serialize(&mut serializer)?;
 
@@ -0,0 +1,141 @@
+use crate::isomorphism::SuitPermutation;
+use crate::log;
+use crate::types::Solution;
+use std::time::Duration;
+use web_time::Instant;
+
+// progress is passed to the sink at most this often
+pub const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+// without a sink, progress is logged at most this often
+const LOG_INTERVAL: Duration = Duration::from_millis(1000);
+// partial solutions are passed to the sink at most this often, building one sorts all hands
+pub const SNAPSHOT_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Progress of a running solve.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Progress {
+    // between 0 and 1
+    pub fraction: f64,
+    pub elapsed: Duration,
+    // extrapolated from the elapsed time, not known before any work is done
+    pub remaining: Option<Duration>,
+}
+
+/// Receives progress of a solve, e.g. calls a JavaScript function or prints an ETA line.
+pub type ProgressSink<'a> = &'a mut dyn FnMut(Progress);
+
+/// Receives provisional solutions of a running solve, extrapolated from the work done so far.
+pub type SnapshotSink<'a> = &'a mut dyn FnMut(Solution);
+
+/// Reports progress of a solve to the sink, or to the log when there is no sink.
+pub struct ProgressReporter<'a> {
+    sink: Option<ProgressSink<'a>>,
+    start: Instant,
+    last: Instant,
+    snapshot_sink: Option<SnapshotSink<'a>>,
+    last_snapshot: Instant,
+    // maps snapshots of a table solved with canonical suits back to the table's suits
+    snapshot_permutation: SuitPermutation,
+}
+
+impl<'a> ProgressReporter<'a> {
+    pub fn new(sink: Option<ProgressSink<'a>>) -> Self {
+        let now = Instant::now();
+        ProgressReporter {
+            sink,
+            start: now,
+            last: now,
+            snapshot_sink: None,
+            last_snapshot: now,
+            snapshot_permutation: SuitPermutation::IDENTITY,
+        }
+    }
+
+    /// Also passes partial solutions to the sink while solving.
+    pub fn with_snapshots(mut self, snapshot_sink: Option<SnapshotSink<'a>>) -> Self {
+        self.snapshot_sink = snapshot_sink;
+        self
+    }
+
+    pub(crate) fn map_snapshots(&mut self, permutation: SuitPermutation) {
+        self.snapshot_permutation = permutation;
+    }
+
+    /// Passes the partial solution to the snapshot sink, if there is one and it's time to.
+    pub(crate) fn snapshot(&mut self, partial_solution: impl FnOnce() -> Solution) {
+        let Some(sink) = &mut self.snapshot_sink else {
+            return;
+        };
+        let now = Instant::now();
+        if now.duration_since(self.last_snapshot) < SNAPSHOT_INTERVAL {
+            return;
+        }
+        sink(self.snapshot_permutation.solution(partial_solution()));
+        self.last_snapshot = now;
+    }
+
+    /// Reports that `done` of `total` units of work (e.g. runouts) are done, `what` describes them
+    /// in the log.
+    pub fn report(&mut self, done: u64, total: u64, what: &str) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.start);
+        match &mut self.sink {
+            Some(sink) => {
+                if now.duration_since(self.last) < PROGRESS_INTERVAL {
+                    return;
+                }
+                sink(progress(done, total, elapsed));
+            }
+            None => {
+                if now.duration_since(self.last) < LOG_INTERVAL {
+                    return;
+                }
+                log(format!("{}/{} {}", done, total, what).as_str());
+            }
+        }
+        self.last = now;
+    }
+
+    /// Reports that all work is done.
+    pub fn finish(&mut self) {
+        if let Some(sink) = &mut self.sink {
+            sink(progress(1, 1, self.start.elapsed()));
+        }
+    }
+}
+
+fn progress(done: u64, total: u64, elapsed: Duration) -> Progress {
+    let fraction = if total == 0 {
+        1.0
+    } else {
+        done as f64 / total as f64
+    };
+    Progress {
+        fraction,
+        elapsed,
+        remaining: (fraction > 0.0).then(|| elapsed.mul_f64((1.0 - fraction) / fraction)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(0, 10, 0.0, None)]
+    #[case(1, 4, 0.25, Some(Duration::from_secs(6)))]
+    #[case(10, 10, 1.0, Some(Duration::ZERO))]
+    fn extrapolates_remaining_time(
+        #[case] done: u64,
+        #[case] total: u64,
+        #[case] fraction: f64,
+        #[case] remaining: Option<Duration>,
+    ) {
+        let result = progress(done, total, Duration::from_secs(2));
+
+        assert_eq!(result.fraction, fraction);
+        assert_eq!(result.elapsed, Duration::from_secs(2));
+        assert_eq!(result.remaining, remaining);
+    }
+}
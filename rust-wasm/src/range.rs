@@ -0,0 +1,331 @@
+use crate::types::{Card, CardSet, Rank, Suit};
+use anyhow::{bail, Context, Result};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Weighted set of two-card hands, parsed from standard range notation, e.g.
+/// `"QQ+, AKs, ATs-A8s, KQo, 76s, AhKh, AJo:0.5"`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Range {
+    hands: Vec<WeightedHand>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct WeightedHand {
+    // sorted from the highest rank, same as candidate hands in the solution
+    pub hand: [Card; 2],
+    pub weight: f64,
+}
+
+impl Range {
+    /// All two-card hands from the deck, with equal weight.
+    pub fn full(deck: &[Card]) -> Range {
+        let mut range = Range::default();
+        for (i, &card1) in deck.iter().enumerate() {
+            for &card2 in &deck[i + 1..] {
+                range.hands.push(WeightedHand {
+                    hand: sorted_hand(card1, card2),
+                    weight: 1.0,
+                })
+            }
+        }
+        range
+    }
+
+    pub fn hands(&self) -> &[WeightedHand] {
+        &self.hands
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hands.is_empty()
+    }
+
+    /// Hands of the range that don't contain any of the cards.
+    pub fn without_cards(&self, cards: CardSet) -> impl Iterator<Item = &WeightedHand> {
+        self.hands
+            .iter()
+            .filter(move |h| !cards.contains(h.hand[0]) && !cards.contains(h.hand[1]))
+    }
+}
+
+impl FromStr for Range {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut range = Range::default();
+        // hand already in the range is overwritten by a later part of the notation
+        let mut hand_indexes: HashMap<[Card; 2], usize> = Default::default();
+        for part in s.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (notation, weight) = match part.split_once(':') {
+                Some((notation, weight)) => {
+                    let weight: f64 = weight
+                        .trim()
+                        .parse()
+                        .with_context(|| format!("invalid weight in \"{part}\""))?;
+                    if !(0.0..=1.0).contains(&weight) {
+                        bail!("weight in \"{part}\" must be between 0 and 1")
+                    }
+                    (notation.trim(), weight)
+                }
+                None => (part, 1.0),
+            };
+            let hands =
+                parse_part(notation).with_context(|| format!("invalid range part \"{part}\""))?;
+            for hand in hands {
+                let weighted = WeightedHand { hand, weight };
+                match hand_indexes.get(&hand) {
+                    Some(&i) => range.hands[i] = weighted,
+                    None => {
+                        hand_indexes.insert(hand, range.hands.len());
+                        range.hands.push(weighted);
+                    }
+                }
+            }
+        }
+        // zero weight only excludes hands, a range with nothing left would have no equity
+        let parsed_hands = range.hands.len();
+        range.hands.retain(|h| h.weight > 0.0);
+        if parsed_hands > 0 && range.hands.is_empty() {
+            bail!("all hands in the range have zero weight")
+        }
+        Ok(range)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Suitedness {
+    Suited,
+    Offsuit,
+    Any,
+}
+
+// pair is a hand class with both ranks equal
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct HandClass {
+    high: Rank,
+    low: Rank,
+    suitedness: Suitedness,
+}
+
+impl HandClass {
+    fn is_pair(&self) -> bool {
+        self.high == self.low
+    }
+
+    fn hands(&self) -> Vec<[Card; 2]> {
+        let mut hands = vec![];
+        for (i, &suit1) in Suit::ALL.iter().enumerate() {
+            for (j, &suit2) in Suit::ALL.iter().enumerate() {
+                let allowed = if self.is_pair() {
+                    i < j
+                } else {
+                    match self.suitedness {
+                        Suitedness::Suited => suit1 == suit2,
+                        Suitedness::Offsuit => suit1 != suit2,
+                        Suitedness::Any => true,
+                    }
+                };
+                if allowed {
+                    hands.push(sorted_hand(
+                        Card {
+                            rank: self.high,
+                            suit: suit1,
+                        },
+                        Card {
+                            rank: self.low,
+                            suit: suit2,
+                        },
+                    ))
+                }
+            }
+        }
+        hands
+    }
+}
+
+fn parse_part(notation: &str) -> Result<Vec<[Card; 2]>> {
+    if let Some(hand) = parse_explicit_hand(notation)? {
+        return Ok(vec![hand]);
+    }
+    let classes = if let Some(class) = notation.strip_suffix('+') {
+        let class = parse_hand_class(class)?;
+        if class.is_pair() {
+            pairs_between(class.high, Rank::A)
+        } else {
+            kickers_between(class, class.low, Rank::ALL[class.high as usize - 1])
+        }
+    } else if let Some((from, to)) = notation.split_once('-') {
+        let from = parse_hand_class(from)?;
+        let to = parse_hand_class(to)?;
+        if from.is_pair() && to.is_pair() {
+            pairs_between(from.low.min(to.low), from.low.max(to.low))
+        } else if from.high == to.high && from.suitedness == to.suitedness {
+            kickers_between(from, from.low.min(to.low), from.low.max(to.low))
+        } else {
+            bail!("both ends must be pairs, or share the highest rank and suitedness")
+        }
+    } else {
+        vec![parse_hand_class(notation)?]
+    };
+    Ok(classes.iter().flat_map(HandClass::hands).collect())
+}
+
+fn pairs_between(from: Rank, to: Rank) -> Vec<HandClass> {
+    Rank::ALL[from as usize..=to as usize]
+        .iter()
+        .map(|&rank| HandClass {
+            high: rank,
+            low: rank,
+            suitedness: Suitedness::Any,
+        })
+        .collect()
+}
+
+fn kickers_between(class: HandClass, from: Rank, to: Rank) -> Vec<HandClass> {
+    Rank::ALL[from as usize..=to as usize]
+        .iter()
+        .map(|&low| HandClass { low, ..class })
+        .collect()
+}
+
+fn parse_hand_class(notation: &str) -> Result<HandClass> {
+    let chars: Vec<char> = notation.trim().chars().collect();
+    let (rank1, rank2, suitedness) = match chars.as_slice() {
+        [r1, r2] => (r1, r2, Suitedness::Any),
+        [r1, r2, 's'] => (r1, r2, Suitedness::Suited),
+        [r1, r2, 'o'] => (r1, r2, Suitedness::Offsuit),
+        _ => bail!("expected two ranks with optional \"s\" or \"o\" suffix"),
+    };
+    let rank1 = parse_rank(*rank1)?;
+    let rank2 = parse_rank(*rank2)?;
+    if rank1 == rank2 && suitedness != Suitedness::Any {
+        bail!("pair can't be suited or offsuit")
+    }
+    Ok(HandClass {
+        high: rank1.max(rank2),
+        low: rank1.min(rank2),
+        suitedness,
+    })
+}
+
+fn parse_explicit_hand(notation: &str) -> Result<Option<[Card; 2]>> {
+    let chars: Vec<char> = notation.chars().collect();
+    let [rank1, suit1, rank2, suit2] = chars.as_slice() else {
+        return Ok(None);
+    };
+    // 4 characters can be also a hand class, e.g. "AKs+"
+    if !is_suit(*suit1) || !is_suit(*suit2) {
+        return Ok(None);
+    }
+    let card1 = Card {
+        rank: parse_rank(*rank1)?,
+        suit: parse_suit(*suit1)?,
+    };
+    let card2 = Card {
+        rank: parse_rank(*rank2)?,
+        suit: parse_suit(*suit2)?,
+    };
+    if card1 == card2 {
+        bail!("hand uses the same card twice")
+    }
+    Ok(Some(sorted_hand(card1, card2)))
+}
+
+fn parse_rank(c: char) -> Result<Rank> {
+    Ok(match c.to_ascii_uppercase() {
+        '2' => Rank::N2,
+        '3' => Rank::N3,
+        '4' => Rank::N4,
+        '5' => Rank::N5,
+        '6' => Rank::N6,
+        '7' => Rank::N7,
+        '8' => Rank::N8,
+        '9' => Rank::N9,
+        'T' => Rank::N10,
+        'J' => Rank::J,
+        'Q' => Rank::Q,
+        'K' => Rank::K,
+        'A' => Rank::A,
+        c => bail!("unrecognized rank \"{c}\""),
+    })
+}
+
+fn is_suit(c: char) -> bool {
+    parse_suit(c).is_ok()
+}
+
+fn parse_suit(c: char) -> Result<Suit> {
+    Ok(match c {
+        'h' => Suit::Hearts,
+        'd' => Suit::Diamonds,
+        's' => Suit::Spades,
+        'c' => Suit::Clubs,
+        c => bail!("unrecognized suit \"{c}\""),
+    })
+}
+
+pub(crate) fn sorted_hand(card1: Card, card2: Card) -> [Card; 2] {
+    let mut hand = [card1, card2];
+    hand.sort_by_key(|c| (Reverse(c.rank), c.suit));
+    hand
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("QQ", 6)]
+    #[case("QQ+", 18)]
+    #[case("22-55", 24)]
+    #[case("AKs", 4)]
+    #[case("AKo", 12)]
+    #[case("AK", 16)]
+    #[case("KA", 16)]
+    #[case("ATs-A8s", 12)]
+    #[case("A8s-ATs", 12)]
+    #[case("K9s+", 16)]
+    #[case("AA+", 6)]
+    #[case("AKs+", 4)]
+    #[case("AhKh", 1)]
+    #[case("QQ+, AKs, ATs-A8s, KQo, 76s", 18 + 4 + 12 + 12 + 4)]
+    #[case("AK, AKs", 16)]
+    #[case("AK, AKs:0", 12)]
+    #[case("", 0)]
+    fn parses_hand_count(#[case] notation: &str, #[case] expected: usize) {
+        let range: Range = notation.parse().unwrap();
+        assert_eq!(range.hands().len(), expected);
+    }
+
+    #[rstest]
+    #[case("AKs-KQs")]
+    #[case("22-AKs")]
+    #[case("AAs")]
+    #[case("AhAh")]
+    #[case("AX")]
+    #[case("AKs:2")]
+    #[case("AKs:0")]
+    #[case("QQ+:0, AKs:0.0")]
+    fn rejects_invalid(#[case] notation: &str) {
+        assert!(notation.parse::<Range>().is_err());
+    }
+
+    #[rstest]
+    fn later_weight_overrides() {
+        let range: Range = "AK:0.5, AKs".parse().unwrap();
+        let suited = range
+            .hands()
+            .iter()
+            .filter(|h| h.hand[0].suit == h.hand[1].suit)
+            .collect::<Vec<_>>();
+        assert_eq!(suited.len(), 4);
+        assert!(suited.iter().all(|h| h.weight == 1.0));
+        assert!(range
+            .hands()
+            .iter()
+            .filter(|h| h.hand[0].suit != h.hand[1].suit)
+            .all(|h| h.weight == 0.5));
+    }
+}
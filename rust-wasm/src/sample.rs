@@ -0,0 +1,462 @@
+use crate::progress::ProgressReporter;
+use crate::solve::{
+    best_combination, best_combination_from_set, multiway_solution, scale_counts, sorted_solution,
+    table_cards, TableCards, YieldTimer,
+};
+use crate::types::{
+    Approximation, Card, CardSet, HandSolution, Solution, Table, COMBINATION_KIND_COUNT,
+};
+use anyhow::{bail, Result};
+use itertools::Itertools;
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+use std::cmp::Ordering;
+use std::time::Duration;
+use web_time::Instant;
+
+// fewer samples don't estimate the standard error well enough, one sample of a hand has zero variance
+pub const MIN_SAMPLES_PER_HAND: u64 = 30;
+
+#[derive(Debug, Clone)]
+pub struct SamplingConfig {
+    // random board completions evaluated against each of opponent's candidate hands
+    pub samples_per_hand: u64,
+    // sampling stops earlier when exceeded, but only after `MIN_SAMPLES_PER_HAND` samples
+    pub time_budget: Option<Duration>,
+    pub seed: u64,
+}
+
+impl Default for SamplingConfig {
+    fn default() -> Self {
+        SamplingConfig {
+            samples_per_hand: 1000,
+            time_budget: None,
+            seed: 0,
+        }
+    }
+}
+
+/// Monte Carlo variant of `solve_with_deck`. Instead of enumerating all board completions, each
+/// candidate hand is evaluated on the same count of randomly dealt completions, so the result has
+/// the same shape as the exact solution, with `board_possibilities` being the count of samples.
+/// With multiple opponents, the remaining opponents' hands are dealt randomly in each sample.
+pub async fn solve_sampled(
+    table: &Table,
+    deck: &[Card],
+    config: &SamplingConfig,
+) -> Result<Solution> {
+    solve_sampled_progress(table, deck, config, &mut ProgressReporter::new(None)).await
+}
+
+pub async fn solve_sampled_progress(
+    table: &Table,
+    deck: &[Card],
+    config: &SamplingConfig,
+    reporter: &mut ProgressReporter<'_>,
+) -> Result<Solution> {
+    if config.samples_per_hand == 0 {
+        bail!("at least 1 sample per hand is needed")
+    }
+    let table_cards = table_cards(table, deck)?;
+    let counts = sample_table_cards(&table_cards, config, reporter, SampledCounts::solution).await;
+    Ok(counts.solution())
+}
+
+/// Counts of sampled runouts, each candidate hand is evaluated on `samples` runouts.
+#[derive(Debug, Clone)]
+pub(crate) struct SampledCounts {
+    pub(crate) hands: Vec<HandSolution>,
+    pub(crate) samples: u64,
+    // index 0 counts wins, index i counts ties with i opponents, only with multiple opponents
+    pub(crate) multiway_counts: Option<Vec<u64>>,
+    pub(crate) combination_counts: [u64; COMBINATION_KIND_COUNT],
+}
+
+impl SampledCounts {
+    pub(crate) fn solution(&self) -> Solution {
+        let approximation = approximation(&self.hands, self.samples, self.multiway_counts.as_ref());
+        let multiway = self.multiway_counts.as_ref().map(|multiway_counts| {
+            multiway_solution(self.samples * self.hands.len() as u64, multiway_counts)
+        });
+        let mut solution = sorted_solution(
+            self.hands.clone(),
+            self.samples,
+            multiway,
+            self.combination_counts,
+        );
+        solution.approximation = Some(approximation);
+        solution
+    }
+
+    /// Solution of all candidate hands of a single opponent, where `exact_hands` are counted on all
+    /// `board_possibilities` runouts, and the sampled hands are scaled up to them. My combination
+    /// counts are taken from all runouts too, the approximation only has the error of sampling.
+    pub(crate) fn solution_with_exact_hands(
+        &self,
+        exact_hands: &[HandSolution],
+        board_possibilities: u64,
+        combination_counts: [u64; COMBINATION_KIND_COUNT],
+    ) -> Solution {
+        let sampled = approximation(&self.hands, self.samples, None);
+        let sampled_weight: f64 = self.hands.iter().map(|hand| hand.weight).sum();
+        let (exact_weight, exact_equity_sum) =
+            exact_hands
+                .iter()
+                .fold((0.0, 0.0), |(weight, equity_sum), hand| {
+                    let equity = (hand.is_beaten_count as f64 + hand.tie_count as f64 / 2.0)
+                        / board_possibilities as f64;
+                    (weight + hand.weight, equity_sum + hand.weight * equity)
+                });
+        let total_weight = exact_weight + sampled_weight;
+        let approximation = Approximation {
+            equity: (exact_equity_sum + sampled_weight * sampled.equity) / total_weight,
+            equity_standard_error: sampled_weight / total_weight * sampled.equity_standard_error,
+            multiway_equity_standard_error: None,
+        };
+
+        let mut hands = exact_hands.to_vec();
+        for hand in &self.hands {
+            let mut hand = hand.clone();
+            let beats_me_count = hand.beats_me_count;
+            let mut outcome_counts = [hand.beats_me_count, hand.is_beaten_count, hand.tie_count];
+            scale_counts(&mut outcome_counts, self.samples, board_possibilities);
+            [hand.beats_me_count, hand.is_beaten_count, hand.tie_count] = outcome_counts;
+            scale_counts(
+                &mut hand.beats_me_combination_counts,
+                beats_me_count,
+                hand.beats_me_count,
+            );
+            hands.push(hand);
+        }
+        let mut solution = sorted_solution(hands, board_possibilities, None, combination_counts);
+        solution.approximation = Some(approximation);
+        solution
+    }
+}
+
+/// Samples runouts of the candidate hands of the table cards, `snapshot` builds provisional
+/// solutions from the counts sampled so far.
+pub(crate) async fn sample_table_cards(
+    table_cards: &TableCards,
+    config: &SamplingConfig,
+    reporter: &mut ProgressReporter<'_>,
+    snapshot: impl Fn(&SampledCounts) -> Solution,
+) -> SampledCounts {
+    let mut yield_timer = YieldTimer::new(Duration::from_millis(50));
+    let start = Instant::now();
+    let mut rng = SmallRng::seed_from_u64(config.seed);
+    let &TableCards {
+        my_cards,
+        fixed_board_cards,
+        ref remaining_deck,
+        choose,
+        opponents,
+        variant,
+        ..
+    } = table_cards;
+    let candidate_cards = table_cards
+        .hands
+        .iter()
+        .map(|hand| hand.hand.iter().collect::<CardSet>())
+        .collect_vec();
+    // cards that can be dealt when opponent holds the candidate hand, shuffled in place
+    let mut candidate_decks = candidate_cards
+        .iter()
+        .map(|&cards| {
+            remaining_deck
+                .iter()
+                .cloned()
+                .filter(|&card| !cards.contains(card))
+                .collect_vec()
+        })
+        .collect_vec();
+    let dealt_count = choose + 2 * (opponents - 1);
+    let mut counts = SampledCounts {
+        hands: table_cards.hands.clone(),
+        samples: 0,
+        multiway_counts: (opponents > 1).then(|| vec![0; opponents + 1]),
+        combination_counts: [0; COMBINATION_KIND_COUNT],
+    };
+
+    while counts.samples < config.samples_per_hand {
+        yield_timer.yield_check().await;
+        let samples = counts.samples;
+        // progress is the elapsed part of the time budget when that runs out first
+        let budget_progress = config
+            .time_budget
+            .map(|budget| {
+                (
+                    start.elapsed().as_millis() as u64,
+                    budget.as_millis() as u64,
+                )
+            })
+            .filter(|&(elapsed, budget)| elapsed * config.samples_per_hand > samples * budget);
+        match budget_progress {
+            Some((elapsed, budget)) => {
+                reporter.report(elapsed, budget, "ms of time budget elapsed")
+            }
+            None => reporter.report(
+                samples,
+                config.samples_per_hand,
+                "samples per hand evaluated",
+            ),
+        }
+        if samples > 0 {
+            reporter.snapshot(|| snapshot(&counts));
+        }
+        if samples >= MIN_SAMPLES_PER_HAND
+            && config
+                .time_budget
+                .is_some_and(|budget| start.elapsed() >= budget)
+        {
+            break;
+        }
+        counts.samples += 1;
+
+        for ((hand, &cards), candidate_deck) in counts
+            .hands
+            .iter_mut()
+            .zip(&candidate_cards)
+            .zip(&mut candidate_decks)
+        {
+            // partial Fisher-Yates shuffle, dealt cards end up at the start of the deck
+            for i in 0..dealt_count {
+                let j = rng.gen_range(i..candidate_deck.len());
+                candidate_deck.swap(i, j);
+            }
+            let (fill_cards, other_opponents_cards) =
+                candidate_deck[..dealt_count].split_at(choose);
+            let board_cards = fixed_board_cards | fill_cards.iter().collect();
+            let my_combination = best_combination_from_set(board_cards | my_cards, variant);
+            counts.combination_counts[my_combination.kind() as usize] += 1;
+            let my_combination = my_combination.score_for(variant);
+            let candidate_combination = best_combination_from_set(board_cards | cards, variant);
+            let candidate = candidate_combination.score_for(variant);
+            match my_combination.cmp(&candidate) {
+                Ordering::Less => {
+                    hand.beats_me_count += 1;
+                    hand.beats_me_combination_counts[candidate_combination.kind() as usize] += 1;
+                }
+                Ordering::Equal => {
+                    hand.tie_count += 1;
+                }
+                Ordering::Greater => {
+                    hand.is_beaten_count += 1;
+                }
+            }
+            if let Some(multiway_counts) = &mut counts.multiway_counts {
+                let mut ties = (candidate == my_combination) as usize;
+                let mut is_beaten = candidate > my_combination;
+                for other_hand in other_opponents_cards.chunks(2) {
+                    let other =
+                        best_combination(board_cards | other_hand.iter().collect(), variant);
+                    ties += (other == my_combination) as usize;
+                    is_beaten |= other > my_combination;
+                }
+                if !is_beaten {
+                    multiway_counts[ties] += 1;
+                }
+            }
+        }
+    }
+
+    reporter.finish();
+    counts
+}
+
+/// Equity estimate against a single opponent, with standard errors. Candidate hands are weighted by
+/// their weight in opponent's range, each with `samples` samples.
+fn approximation(
+    hands: &[HandSolution],
+    samples: u64,
+    multiway_counts: Option<&Vec<u64>>,
+) -> Approximation {
+    let samples = samples as f64;
+    let total_weight: f64 = hands.iter().map(|hand| hand.weight).sum();
+    let mut equity = 0.0;
+    let mut variance = 0.0;
+    for hand in hands {
+        let (mean, mean_square) = share_moments(
+            &[
+                (hand.is_beaten_count as f64, 1.0),
+                (hand.tie_count as f64, 0.5),
+            ],
+            samples,
+        );
+        let weight = hand.weight / total_weight;
+        equity += weight * mean;
+        variance += weight * weight * (mean_square - mean * mean) / samples;
+    }
+
+    let multiway_equity_standard_error = multiway_counts.map(|counts| {
+        let possibilities = samples * hands.len() as f64;
+        let shares = counts
+            .iter()
+            .enumerate()
+            .map(|(ties, &count)| (count as f64, 1.0 / (ties + 1) as f64))
+            .collect_vec();
+        let (mean, mean_square) = share_moments(&shares, possibilities);
+        ((mean_square - mean * mean) / possibilities).sqrt()
+    });
+
+    Approximation {
+        equity,
+        equity_standard_error: variance.sqrt(),
+        multiway_equity_standard_error,
+    }
+}
+
+/// Mean and mean of squares of the pot share, from counts of samples with each share (samples not
+/// listed have zero share).
+fn share_moments(counts_and_shares: &[(f64, f64)], samples: f64) -> (f64, f64) {
+    let (sum, sum_squares) =
+        counts_and_shares
+            .iter()
+            .fold((0.0, 0.0), |(sum, sum_squares), &(count, share)| {
+                (sum + count * share, sum_squares + count * share * share)
+            });
+    (sum / samples, sum_squares / samples)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solve::{full_deck, solve_with_deck};
+    use futures::executor::block_on;
+    use rstest::rstest;
+
+    #[rstest]
+    fn sampled_river_matches_exact() {
+        let deck = full_deck();
+        let table = Table {
+            hand: vec![deck[5], deck[6]].into_boxed_slice(),
+            board: vec![
+                Some(deck[7]),
+                Some(deck[8]),
+                Some(deck[20]),
+                Some(deck[33]),
+                Some(deck[50]),
+            ]
+            .into_boxed_slice(),
+            ..Default::default()
+        };
+        let exact = block_on(solve_with_deck(&table, &deck)).unwrap();
+        let config = SamplingConfig {
+            samples_per_hand: 10,
+            ..Default::default()
+        };
+        let sampled = block_on(solve_sampled(&table, &deck, &config)).unwrap();
+
+        // river has only one possible board, every sample is the same
+        assert_eq!(sampled.board_possibilities, 10);
+        for (exact_hand, sampled_hand) in exact.hands.iter().zip(sampled.hands.iter()) {
+            assert_eq!(exact_hand.hand, sampled_hand.hand);
+            assert_eq!(exact_hand.beats_me_count * 10, sampled_hand.beats_me_count);
+            assert_eq!(
+                exact_hand.is_beaten_count * 10,
+                sampled_hand.is_beaten_count
+            );
+        }
+        let approximation = sampled.approximation.unwrap();
+        assert!((approximation.equity - exact.equity()).abs() < 1e-12);
+        assert_eq!(approximation.equity_standard_error, 0.0);
+    }
+
+    #[rstest]
+    fn sampled_flop_is_close_to_exact() {
+        let deck = full_deck();
+        let table = Table {
+            hand: vec![deck[0], deck[13]].into_boxed_slice(),
+            board: vec![Some(deck[26]), Some(deck[1]), Some(deck[40]), None, None]
+                .into_boxed_slice(),
+            ..Default::default()
+        };
+        let exact = block_on(solve_with_deck(&table, &deck)).unwrap();
+        let config = SamplingConfig {
+            samples_per_hand: 20,
+            ..Default::default()
+        };
+        let sampled = block_on(solve_sampled(&table, &deck, &config)).unwrap();
+        let approximation = sampled.approximation.unwrap();
+
+        assert_eq!(sampled.hands.len(), exact.hands.len());
+        assert!(approximation.equity_standard_error > 0.0);
+        assert!(
+            (approximation.equity - exact.equity()).abs()
+                < 4.0 * approximation.equity_standard_error
+        );
+    }
+
+    #[rstest]
+    fn sampled_multiway_is_close_to_exact() {
+        let deck = full_deck();
+        let table = Table {
+            hand: vec![deck[12], deck[11]].into_boxed_slice(),
+            board: vec![
+                Some(deck[10]),
+                Some(deck[22]),
+                Some(deck[3]),
+                Some(deck[40]),
+                None,
+            ]
+            .into_boxed_slice(),
+            players: 3,
+            ..Default::default()
+        };
+        let exact = block_on(solve_with_deck(&table, &deck)).unwrap();
+        let config = SamplingConfig {
+            samples_per_hand: 5,
+            ..Default::default()
+        };
+        let sampled = block_on(solve_sampled(&table, &deck, &config)).unwrap();
+        let standard_error = sampled
+            .approximation
+            .unwrap()
+            .multiway_equity_standard_error
+            .unwrap();
+        let multiway = sampled.multiway.unwrap();
+
+        assert_eq!(multiway.possibilities, 5 * sampled.hands.len() as u64);
+        assert!(standard_error > 0.0);
+        assert!(
+            (multiway.equity() - exact.multiway.unwrap().equity()).abs() < 4.0 * standard_error
+        );
+    }
+
+    #[rstest]
+    fn sampling_without_samples_fails() {
+        let deck = full_deck();
+        let table = Table {
+            hand: vec![deck[0], deck[13]].into_boxed_slice(),
+            ..Default::default()
+        };
+        let config = SamplingConfig {
+            samples_per_hand: 0,
+            ..Default::default()
+        };
+
+        assert!(block_on(solve_sampled(&table, &deck, &config)).is_err());
+    }
+
+    #[rstest]
+    fn sampling_is_deterministic_with_seed() {
+        let deck = full_deck();
+        let table = Table {
+            hand: vec![deck[0], deck[13]].into_boxed_slice(),
+            ..Default::default()
+        };
+        let config = SamplingConfig {
+            samples_per_hand: 3,
+            seed: 42,
+            ..Default::default()
+        };
+        let first = block_on(solve_sampled(&table, &deck, &config)).unwrap();
+        let second = block_on(solve_sampled(&table, &deck, &config)).unwrap();
+
+        for (first_hand, second_hand) in first.hands.iter().zip(second.hands.iter()) {
+            assert_eq!(first_hand.hand, second_hand.hand);
+            assert_eq!(first_hand.beats_me_count, second_hand.beats_me_count);
+            assert_eq!(first_hand.is_beaten_count, second_hand.is_beaten_count);
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use crate::isomorphism::SymmetricEvaluator;
+use crate::solve::{next_combination, table_cards, RunoutCounts, YieldTimer};
+use crate::types::{Card, CardSet, Solution, Table};
+use anyhow::{bail, Result};
+use std::time::Duration;
+
+/// Evaluates every `shards`-th runout of the table that isn't skipped as symmetric to another
+/// runout, starting with the runout `shard`, so that shards have about the same work. Shards are
+/// solved separately, e.g. by web workers each with its own wasm instance, and then merged with
+/// `merge_shards_with_deck`.
+pub async fn solve_shard_with_deck(
+    table: &Table,
+    deck: &[Card],
+    shard: usize,
+    shards: usize,
+) -> Result<RunoutCounts> {
+    if shard >= shards {
+        bail!("shard {shard} is out of {shards} shards")
+    }
+    let mut yield_timer = YieldTimer::new(Duration::from_millis(50));
+    let table_cards = table_cards(table, deck)?;
+    let remaining_deck = &table_cards.remaining_deck;
+    let mut evaluator = SymmetricEvaluator::new(&table_cards);
+    let mut counts = RunoutCounts::new(&table_cards);
+
+    let mut fill_cards_map_i = Vec::from_iter(0..table_cards.choose);
+    // index among the evaluated runouts
+    let mut runout_i = 0;
+    loop {
+        let fill_cards: CardSet = fill_cards_map_i
+            .iter()
+            .map(|&i| remaining_deck[i])
+            .collect();
+        if evaluator.is_representative(fill_cards) {
+            if runout_i % shards == shard {
+                yield_timer.yield_check().await;
+                evaluator.evaluate(fill_cards, &mut counts);
+            }
+            runout_i += 1;
+        }
+
+        if !next_combination(&mut fill_cards_map_i, remaining_deck.len()) {
+            break;
+        }
+    }
+    Ok(counts)
+}
+
+/// Solution of the table from the counts of all its shards.
+pub fn merge_shards_with_deck(
+    table: &Table,
+    deck: &[Card],
+    shards: Vec<RunoutCounts>,
+) -> Result<Solution> {
+    let table_cards = table_cards(table, deck)?;
+    let mut counts = RunoutCounts::new(&table_cards);
+    for shard in shards {
+        let same_hands = shard.hands.len() == counts.hands.len()
+            && shard
+                .hands
+                .iter()
+                .zip(&counts.hands)
+                .all(|(a, b)| a.hand == b.hand);
+        if !same_hands || shard.multiway_counts.len() != counts.multiway_counts.len() {
+            bail!("shard counts are not of this table")
+        }
+        counts = counts.merge(shard);
+    }
+    Ok(counts.into_solution(&table_cards))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solve::{full_deck, solve_with_deck};
+    use futures::executor::block_on;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(2, 1)]
+    #[case(2, 3)]
+    #[case(3, 4)]
+    fn merged_shards_match_solve(#[case] players: usize, #[case] shards: usize) {
+        let deck = full_deck();
+        let table = Table {
+            hand: vec![deck[12], deck[11]].into_boxed_slice(),
+            board: vec![Some(deck[10]), Some(deck[22]), Some(deck[3]), None, None].into(),
+            players,
+            ..Default::default()
+        };
+        let shard_counts = (0..shards)
+            .map(|shard| block_on(solve_shard_with_deck(&table, &deck, shard, shards)).unwrap())
+            .collect();
+        let solution = merge_shards_with_deck(&table, &deck, shard_counts).unwrap();
+        let expected = block_on(solve_with_deck(&table, &deck)).unwrap();
+
+        assert_eq!(
+            serde_json::to_string(&solution).unwrap(),
+            serde_json::to_string(&expected).unwrap()
+        );
+    }
+
+    #[rstest]
+    fn rejects_shards_of_other_table() {
+        let deck = full_deck();
+        let table = Table {
+            hand: vec![deck[12], deck[11]].into_boxed_slice(),
+            board: vec![
+                Some(deck[10]),
+                Some(deck[22]),
+                Some(deck[3]),
+                Some(deck[4]),
+                None,
+            ]
+            .into(),
+            ..Default::default()
+        };
+        let other_table = Table {
+            hand: vec![deck[12], deck[13]].into_boxed_slice(),
+            ..table.clone()
+        };
+        let shard = block_on(solve_shard_with_deck(&other_table, &deck, 0, 1)).unwrap();
+
+        assert!(merge_shards_with_deck(&table, &deck, vec![shard]).is_err());
+        assert!(block_on(solve_shard_with_deck(&table, &deck, 2, 2)).is_err());
+    }
+}
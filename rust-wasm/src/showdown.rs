@@ -0,0 +1,207 @@
+use crate::solve::{best_combination, check_used_cards, n_choose_m, next_combination, YieldTimer};
+use crate::types::{Card, CardSet, PlayerShowdown, ShowdownSolution, Table};
+use anyhow::{bail, Result};
+use itertools::Itertools;
+use std::time::Duration;
+
+pub const MAX_SHOWDOWN_PLAYERS: usize = 10;
+
+/// Showdown of my hand against all opponents' known hands, enumerating every board completion.
+pub async fn solve_showdown_with_deck(table: &Table, deck: &[Card]) -> Result<ShowdownSolution> {
+    let mut yield_timer = YieldTimer::new(Duration::from_millis(50));
+    let players = 1 + table.known_hands.len();
+    if !(2..=MAX_SHOWDOWN_PLAYERS).contains(&players) {
+        bail!("showdown needs 2 to {MAX_SHOWDOWN_PLAYERS} hands, got {players}")
+    }
+    if table.players != players {
+        bail!(
+            "table has {} players, but {players} hands are known",
+            table.players
+        )
+    }
+    if table.opponent_range.is_some() {
+        bail!("opponent range can't be used with known opponents' hands")
+    }
+    let mut hands = vec![table.hand.to_vec()];
+    hands.extend(table.known_hands.iter().map(|hand| hand.to_vec()));
+    let deck_set: CardSet = deck.iter().collect();
+    let used_cards = check_used_cards(
+        hands
+            .iter()
+            .flatten()
+            .chain(table.board.iter().flatten())
+            .chain(table.dead.iter()),
+        deck_set,
+    )?;
+    let remaining_deck = deck
+        .iter()
+        .cloned()
+        .filter(|&card| !used_cards.contains(card))
+        .collect_vec();
+    let choose = table.board.iter().filter(|x| x.is_none()).count();
+    if remaining_deck.len() < choose {
+        bail!("not enough cards in deck to complete the board")
+    }
+    let fixed_board_cards: CardSet = table.board.iter().flatten().collect();
+    let hand_cards = hands
+        .iter()
+        .map(|hand| hand.iter().collect::<CardSet>())
+        .collect_vec();
+
+    let mut win_counts = vec![0u64; players];
+    // split_counts[i][j] is the count of pots split by player i with j + 2 players
+    let mut split_counts = vec![vec![0u64; players - 1]; players];
+    let mut scores = vec![0u64; players];
+    let mut fill_cards_map_i = Vec::from_iter(0..choose);
+    loop {
+        yield_timer.yield_check().await;
+        let board_cards = fixed_board_cards
+            | fill_cards_map_i
+                .iter()
+                .map(|&i| remaining_deck[i])
+                .collect();
+        for (score, &cards) in scores.iter_mut().zip(&hand_cards) {
+            *score = best_combination(board_cards | cards, table.variant);
+        }
+        let best_score = *scores.iter().max().unwrap();
+        let winners = scores.iter().filter(|&&score| score == best_score).count();
+        for (i, &score) in scores.iter().enumerate() {
+            if score != best_score {
+                continue;
+            }
+            if winners == 1 {
+                win_counts[i] += 1;
+            } else {
+                split_counts[i][winners - 2] += 1;
+            }
+        }
+
+        if !next_combination(&mut fill_cards_map_i, remaining_deck.len()) {
+            break;
+        }
+    }
+
+    let board_possibilities = n_choose_m(remaining_deck.len(), choose);
+    Ok(ShowdownSolution {
+        board_possibilities,
+        players: hands
+            .into_iter()
+            .zip(win_counts)
+            .zip(split_counts)
+            .map(|((hand, win_count), split_counts)| {
+                let split_shares: f64 = split_counts
+                    .iter()
+                    .enumerate()
+                    .map(|(j, &count)| count as f64 / (j + 2) as f64)
+                    .sum();
+                PlayerShowdown {
+                    hand: hand.into(),
+                    win_count,
+                    tie_count: split_counts.iter().sum(),
+                    equity: (win_count as f64 + split_shares) / board_possibilities as f64,
+                }
+            })
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solve::{full_deck, solve_with_deck};
+    use futures::executor::block_on;
+    use rstest::rstest;
+
+    #[rstest]
+    fn heads_up_showdown_matches_single_hand_solve() {
+        let deck = full_deck();
+        let board = vec![Some(deck[10]), Some(deck[22]), Some(deck[3]), None, None];
+        let opponent_hand = [deck[25], deck[38]];
+        let table = Table {
+            hand: vec![deck[12], deck[11]].into_boxed_slice(),
+            board: board.into_boxed_slice(),
+            known_hands: vec![opponent_hand].into_boxed_slice(),
+            ..Default::default()
+        };
+        let result = block_on(solve_showdown_with_deck(&table, &deck)).unwrap();
+
+        let range_table = Table {
+            known_hands: Default::default(),
+            opponent_range: Some("AdAs".parse().unwrap()),
+            ..table
+        };
+        let expected = block_on(solve_with_deck(&range_table, &deck)).unwrap();
+        let expected_hand = &expected.hands[0];
+
+        assert_eq!(result.board_possibilities, expected.board_possibilities);
+        assert_eq!(result.players[0].win_count, expected_hand.is_beaten_count);
+        assert_eq!(result.players[0].tie_count, expected_hand.tie_count);
+        assert_eq!(result.players[1].win_count, expected_hand.beats_me_count);
+        assert!((result.players[0].equity - expected.equity()).abs() < 1e-12);
+        assert!((result.players[0].equity + result.players[1].equity - 1.0).abs() < 1e-12);
+    }
+
+    #[rstest]
+    fn multiway_equities_sum_to_one() {
+        let deck = full_deck();
+        let table = Table {
+            hand: vec![deck[12], deck[25]].into_boxed_slice(),
+            board: vec![Some(deck[0]), Some(deck[14]), Some(deck[30]), None, None]
+                .into_boxed_slice(),
+            players: 4,
+            known_hands: vec![
+                [deck[11], deck[24]],
+                [deck[5], deck[6]],
+                [deck[40], deck[41]],
+            ]
+            .into_boxed_slice(),
+            ..Default::default()
+        };
+        let result = block_on(solve_showdown_with_deck(&table, &deck)).unwrap();
+
+        assert_eq!(result.board_possibilities, n_choose_m(52 - 8 - 3, 2));
+        let equity_sum: f64 = result.players.iter().map(|p| p.equity).sum();
+        assert!((equity_sum - 1.0).abs() < 1e-12);
+    }
+
+    #[rstest]
+    fn same_hands_split_every_pot() {
+        let deck = full_deck();
+        // AK against AK of other suits, on a board where no flush is possible
+        let table = Table {
+            hand: vec![deck[12], deck[24]].into_boxed_slice(),
+            board: vec![
+                Some(deck[0]),
+                Some(deck[14]),
+                Some(deck[28]),
+                Some(deck[42]),
+                None,
+            ]
+            .into_boxed_slice(),
+            known_hands: vec![[deck[38], deck[50]]].into_boxed_slice(),
+            ..Default::default()
+        };
+        let result = block_on(solve_showdown_with_deck(&table, &deck)).unwrap();
+
+        for player in result.players.iter() {
+            assert_eq!(player.win_count, 0);
+            assert_eq!(player.tie_count, result.board_possibilities);
+            assert_eq!(player.equity, 0.5);
+        }
+    }
+
+    #[rstest]
+    #[case(vec![], 2)]
+    #[case(vec![[full_deck()[25], full_deck()[38]]], 3)]
+    #[case(vec![[full_deck()[12], full_deck()[38]]], 2)]
+    fn rejects_invalid_showdown(#[case] known_hands: Vec<[Card; 2]>, #[case] players: usize) {
+        let deck = full_deck();
+        let table = Table {
+            hand: vec![deck[12], deck[11]].into_boxed_slice(),
+            players,
+            known_hands: known_hands.into_boxed_slice(),
+            ..Default::default()
+        };
+        assert!(block_on(solve_showdown_with_deck(&table, &deck)).is_err());
+    }
+}
@@ -1,3 +1,5 @@
+use futures::future;
+use futures::future::Either;
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::{Arc, Mutex};
@@ -7,7 +9,7 @@ use wasm_bindgen::prelude::wasm_bindgen;
 pub(crate) struct DropDetector<T, F>
 where
     T: Copy,
-    F: Fn(T) -> (),
+    F: Fn(T),
 {
     pub(crate) s: T,
     pub(crate) f: F,
@@ -16,7 +18,7 @@ where
 impl<T, F> Drop for DropDetector<T, F>
 where
     T: Copy,
-    F: Fn(T) -> (),
+    F: Fn(T),
 {
     fn drop(&mut self) {
         (self.f)(self.s)
@@ -31,10 +33,19 @@ pub struct AbortSignal {
 
 struct AbortState {
     aborted: bool,
-    waker: Option<Waker>,
+    next_waiter_id: u64,
+    // wakers of the pending `Aborted` futures, by their ids
+    wakers: Vec<(u64, Waker)>,
 }
 
-impl Future for AbortSignal {
+/// Completes once the signal is aborted. Any number of these can wait for the same signal, each is
+/// forgotten by the signal when dropped.
+pub(crate) struct Aborted {
+    state: Arc<Mutex<AbortState>>,
+    id: u64,
+}
+
+impl Future for Aborted {
     type Output = ();
 
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
@@ -42,11 +53,39 @@ impl Future for AbortSignal {
         if state.aborted {
             return Poll::Ready(());
         }
-        state.waker = Some(cx.waker().clone()).into();
+        match state.wakers.iter_mut().find(|(id, _)| *id == self.id) {
+            Some((_, waker)) => waker.clone_from(cx.waker()),
+            None => state.wakers.push((self.id, cx.waker().clone())),
+        }
         Poll::Pending
     }
 }
 
+impl Drop for Aborted {
+    fn drop(&mut self) {
+        let mut state = self.state.lock().unwrap();
+        state.wakers.retain(|(id, _)| *id != self.id);
+    }
+}
+
+impl Default for AbortSignal {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AbortSignal {
+    pub(crate) fn aborted_future(&self) -> Aborted {
+        let mut state = self.state.lock().unwrap();
+        let id = state.next_waiter_id;
+        state.next_waiter_id += 1;
+        Aborted {
+            state: self.state.clone(),
+            id,
+        }
+    }
+}
+
 #[wasm_bindgen]
 impl AbortSignal {
     #[wasm_bindgen(constructor)]
@@ -54,7 +93,8 @@ impl AbortSignal {
         AbortSignal {
             state: Arc::new(Mutex::new(AbortState {
                 aborted: false,
-                waker: None.into(),
+                next_waiter_id: 0,
+                wakers: vec![],
             })),
         }
     }
@@ -65,10 +105,56 @@ impl AbortSignal {
         state.aborted
     }
 
+    // resolves to true once aborted, so that the abort can be forwarded e.g. to web workers, or to
+    // false once `finished` is aborted, so that the forwarding isn't kept after the work is done
+    pub async fn wait(&self, finished: &AbortSignal) -> bool {
+        match future::select(self.aborted_future(), finished.aborted_future()).await {
+            Either::Left(_) => true,
+            Either::Right(_) => false,
+        }
+    }
+
     #[wasm_bindgen]
     pub fn abort(&self) {
         let mut state = self.state.lock().unwrap();
         state.aborted = true;
-        state.waker.take().map(|waker| waker.wake());
+        for (_, waker) in state.wakers.drain(..) {
+            waker.wake();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use futures::FutureExt;
+    use rstest::rstest;
+
+    #[rstest]
+    fn abort_wakes_all_waiters() {
+        let signal = AbortSignal::new();
+        let finished = AbortSignal::new();
+        let cancelled = async { signal.aborted_future().await };
+        let forwarded = signal.wait(&finished);
+        futures::pin_mut!(cancelled, forwarded);
+        assert!(cancelled.as_mut().now_or_never().is_none());
+        assert!(forwarded.as_mut().now_or_never().is_none());
+        assert_eq!(signal.state.lock().unwrap().wakers.len(), 2);
+
+        signal.abort();
+
+        assert_eq!(cancelled.now_or_never(), Some(()));
+        assert_eq!(forwarded.now_or_never(), Some(true));
+    }
+
+    #[rstest]
+    fn finished_wait_forgets_its_waker() {
+        let signal = AbortSignal::new();
+        let finished = AbortSignal::new();
+        finished.abort();
+
+        assert!(!block_on(signal.wait(&finished)));
+        assert!(signal.state.lock().unwrap().wakers.is_empty());
     }
 }
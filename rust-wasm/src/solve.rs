@@ -1,6 +1,18 @@
+use crate::isomorphism::{canonical_permutation, SuitPermutation, SymmetricEvaluator};
+use crate::next_card::solve_next_cards_with_deck;
+use crate::progress::ProgressReporter;
+use crate::range::Range;
+use crate::sample::{
+    sample_table_cards, solve_sampled, solve_sampled_progress, SamplingConfig, MIN_SAMPLES_PER_HAND,
+};
+use crate::shard::solve_shard_with_deck;
+use crate::showdown::solve_showdown_with_deck;
+use crate::solver::{Outcome, RunoutRecord, Solver};
 use crate::types::{
-    Card, Combination, HandSolution, PrecalculatedSolution, Rank, ReducedCard, Solution, Suit,
-    Table, BOARD_SIZE, COMBINATION_SIZE, RANK_COUNT, SUIT_COUNT,
+    Card, CardSet, Combination, HandSolution, MultiwaySolution, NextCardsSolution,
+    PrecalculatedSolution, RangeHandSolution, RangeSolution, Rank, ShowdownSolution, Solution,
+    Suit, Table, Variant, BOARD_SIZE, COMBINATION_KIND_COUNT, COMBINATION_SIZE, DEFAULT_PLAYERS,
+    RANK_COUNT,
 };
 use crate::{log, read_file, signal, ROOT_PATH};
 use anyhow::{bail, Context, Ok, Result};
@@ -9,24 +21,201 @@ use async_std::task;
 use futures::future;
 use futures::future::Either;
 use itertools::Itertools;
-use serde::Deserialize;
-use std::cmp::{Ordering, Reverse};
-use std::collections::{HashMap, HashSet};
+use serde::Deserialize as _;
+use serde_derive::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::future::Future;
 use std::time::Duration;
 use strum::IntoEnumIterator;
 use web_time::Instant;
 
+// sets of opponents' hands over all runouts, above which a multiway table is sampled when solved
+// with a sampling config; a flop with 2 opponents stays below it
+const MAX_ENUMERATED_MULTIWAY_SETS: f64 = 1e9;
+
+/// Pre-flop tables without a precalculated solution, and large multiway tables, are sampled with
+/// the default config, and the solution has an approximation.
 pub async fn solve(cancellation_token: signal::AbortSignal, table: &Table) -> Result<Solution> {
-    if table.board.iter().filter(|c| c.is_none()).count() == BOARD_SIZE {
-        return Ok(get_precalculated_solution(&table.hand).await?);
+    solve_with_progress(
+        cancellation_token,
+        table,
+        Some(&SamplingConfig::default()),
+        &mut ProgressReporter::new(None),
+    )
+    .await
+}
+
+/// Pre-flop tables without a precalculated solution, and multiway tables with too many sets of
+/// opponents' hands, are sampled with `sampling`, because full enumeration of them is too slow.
+/// `None` enumerates them anyway.
+pub async fn solve_with_progress(
+    cancellation_token: signal::AbortSignal,
+    table: &Table,
+    sampling: Option<&SamplingConfig>,
+    reporter: &mut ProgressReporter<'_>,
+) -> Result<Solution> {
+    let deck = deck(table.variant);
+    if is_preflop(table) {
+        if let Some(solution) = precalculated_solution(table).await? {
+            return Ok(solution);
+        }
+    }
+    if let Some(config) = sampling {
+        if is_preflop(table) || is_multiway_too_large(&table_cards(table, &deck)?) {
+            return cancellable(
+                cancellation_token,
+                solve_sampled_progress(table, &deck, config, reporter),
+            )
+            .await;
+        }
+    }
+
+    cancellable(
+        cancellation_token,
+        solve_with_deck_progress(table, &deck, reporter),
+    )
+    .await
+}
+
+/// Like `solve_with_progress`, but the solution is approximate when solving it exactly would take
+/// longer than the time budget.
+pub async fn solve_with_time_budget(
+    cancellation_token: signal::AbortSignal,
+    table: &Table,
+    time_budget: Duration,
+    reporter: &mut ProgressReporter<'_>,
+) -> Result<Solution> {
+    if is_preflop(table) {
+        if let Some(solution) = precalculated_solution(table).await? {
+            return Ok(solution);
+        }
+    }
+    let deck = deck(table.variant);
+    cancellable(
+        cancellation_token,
+        solve_with_deck_time_budget(table, &deck, time_budget, reporter),
+    )
+    .await
+}
+
+// no board card is known yet
+pub(crate) fn is_preflop(table: &Table) -> bool {
+    table.board.iter().filter(|c| c.is_none()).count() == BOARD_SIZE
+}
+
+// every runout is evaluated against each set of opponents' hands that doesn't beat me, so their
+// count grows exponentially with the number of opponents
+fn is_multiway_too_large(table_cards: &TableCards) -> bool {
+    let remaining_cards = table_cards.remaining_deck.len();
+    let runouts = n_choose_m(remaining_cards, table_cards.choose) as f64;
+    // in floating point, as the product overflows with many opponents
+    let hands_sets = (0..table_cards.opponents)
+        .map(|i| {
+            n_choose_m(remaining_cards - table_cards.choose - 2 * i, 2) as f64 / (i + 1) as f64
+        })
+        .product::<f64>();
+    table_cards.opponents > 1 && runouts * hands_sets > MAX_ENUMERATED_MULTIWAY_SETS
+}
+
+/// Precalculated solution of a pre-flop table, if the table has one.
+async fn precalculated_solution(table: &Table) -> Result<Option<Solution>> {
+    // precalculated solution assumes the full deck
+    if table.players != DEFAULT_PLAYERS
+        || table.opponent_range.is_some()
+        || !table.dead.is_empty()
+        || !table.known_hands.is_empty()
+    {
+        return Ok(None);
+    }
+    let solution = get_precalculated_solution(&table.hand, table.variant).await;
+    if table.variant == Variant::Holdem {
+        return solution.map(Some);
+    }
+    // short-deck solutions are deployed only when precalculated, otherwise pre-flop is sampled
+    Ok(solution
+        .inspect_err(|e| log(format!("no precalculated solutions: {:#}", e).as_str()))
+        .ok())
+}
+
+pub async fn solve_ranges(
+    cancellation_token: signal::AbortSignal,
+    my_range: &Range,
+    opponent_range: &Range,
+    board: &[Option<Card>],
+    variant: Variant,
+) -> Result<RangeSolution> {
+    let deck = deck(variant);
+    cancellable(
+        cancellation_token,
+        solve_range_vs_range(my_range, opponent_range, board, &deck, variant),
+    )
+    .await
+}
+
+pub async fn solve_sampled_table(
+    cancellation_token: signal::AbortSignal,
+    table: &Table,
+    config: &SamplingConfig,
+) -> Result<Solution> {
+    let deck = deck(table.variant);
+    cancellable(cancellation_token, solve_sampled(table, &deck, config)).await
+}
+
+pub async fn solve_next_cards(
+    cancellation_token: signal::AbortSignal,
+    table: &Table,
+) -> Result<NextCardsSolution> {
+    let deck = deck(table.variant);
+    cancellable(cancellation_token, solve_next_cards_with_deck(table, &deck)).await
+}
+
+pub async fn solve_showdown(
+    cancellation_token: signal::AbortSignal,
+    table: &Table,
+) -> Result<ShowdownSolution> {
+    let deck = deck(table.variant);
+    cancellable(cancellation_token, solve_showdown_with_deck(table, &deck)).await
+}
+
+pub async fn solve_shard(
+    cancellation_token: signal::AbortSignal,
+    table: &Table,
+    shard: usize,
+    shards: usize,
+) -> Result<RunoutCounts> {
+    let deck = deck(table.variant);
+    cancellable(
+        cancellation_token,
+        solve_shard_with_deck(table, &deck, shard, shards),
+    )
+    .await
+}
+
+/// Solves the table reusing the runouts of the previous table solved by the solver, when the table
+/// only has one more board card. Pre-flop tables, and multiway tables that are too large, are
+/// solved as by `solve_with_progress`.
+pub async fn solve_incremental(
+    cancellation_token: signal::AbortSignal,
+    solver: &mut Solver,
+    table: &Table,
+    sampling: Option<&SamplingConfig>,
+    reporter: &mut ProgressReporter<'_>,
+) -> Result<Solution> {
+    let deck = deck(table.variant);
+    if is_preflop(table) || is_multiway_too_large(&table_cards(table, &deck)?) {
+        solver.clear();
+        return solve_with_progress(cancellation_token, table, sampling, reporter).await;
     }
+    cancellable(cancellation_token, solver.solve(table, &deck, reporter)).await
+}
 
-    let deck = full_deck();
-    let mut cache = Default::default();
-    let fut = solve_with_deck(table, &deck, &mut cache);
+async fn cancellable<T>(
+    cancellation_token: signal::AbortSignal,
+    fut: impl Future<Output = Result<T>>,
+) -> Result<T> {
     futures::pin_mut!(fut);
 
-    match future::select(fut, cancellation_token).await {
+    match future::select(fut, cancellation_token.aborted_future()).await {
         Either::Left((result, _)) => result,
         Either::Right(_) => {
             bail!("solve operation cancelled")
@@ -35,12 +224,39 @@ pub async fn solve(cancellation_token: signal::AbortSignal, table: &Table) -> Re
 }
 
 static SOLUTIONS: OnceCell<Box<[PrecalculatedSolution]>> = OnceCell::new();
+static SHORT_DECK_SOLUTIONS: OnceCell<Box<[PrecalculatedSolution]>> = OnceCell::new();
+static SHORT_DECK_TRIPS_SOLUTIONS: OnceCell<Box<[PrecalculatedSolution]>> = OnceCell::new();
+
+/// Name of the file with precalculated pre-flop solutions of the variant, in the `precalculated`
+/// directory.
+pub fn precalculated_solutions_file(variant: Variant) -> &'static str {
+    match variant {
+        Variant::Holdem => "preflop_solutions.json",
+        Variant::ShortDeck {
+            trips_beat_straight: false,
+        } => "preflop_solutions_short_deck.json",
+        Variant::ShortDeck {
+            trips_beat_straight: true,
+        } => "preflop_solutions_short_deck_trips.json",
+    }
+}
 
-async fn get_precalculated_solution(hand: &Box<[Card]>) -> Result<Solution> {
-    let solutions = SOLUTIONS
+async fn get_precalculated_solution(hand: &[Card], variant: Variant) -> Result<Solution> {
+    let solutions = match variant {
+        Variant::Holdem => &SOLUTIONS,
+        Variant::ShortDeck {
+            trips_beat_straight: false,
+        } => &SHORT_DECK_SOLUTIONS,
+        Variant::ShortDeck {
+            trips_beat_straight: true,
+        } => &SHORT_DECK_TRIPS_SOLUTIONS,
+    };
+    let solutions = solutions
         .get_or_try_init(async {
-            let precalculated_solutions_bytes =
-                read_file(&(ROOT_PATH.to_owned() + "precalculated/preflop_solutions.json")).await?;
+            let precalculated_solutions_bytes = read_file(
+                &(ROOT_PATH.to_owned() + "precalculated/" + precalculated_solutions_file(variant)),
+            )
+            .await?;
             let mut deserializer =
                 serde_json::Deserializer::from_slice(&precalculated_solutions_bytes);
             let solutions = Vec::<PrecalculatedSolution>::deserialize(&mut deserializer)?;
@@ -49,387 +265,1086 @@ async fn get_precalculated_solution(hand: &Box<[Card]>) -> Result<Solution> {
         .await?;
 
     // Precalculated solution contains only hands with heart diamond (offsuit) or heart-heart
-    // (suited) cards.
+    // (suited) cards, which is the canonical form of the hand.
     // Retrieve solution using such hand, and then re-map all suits in the retrieved solution so
     // that odds don't change.
 
-    let mut suit_isomorphic_representative: Vec<Card> = Vec::from(hand.as_ref());
-    let mut suit_isomorphism: HashMap<Suit, Suit> = Default::default();
-    let mut unmapped_suits: HashSet<Suit> = HashSet::from_iter(Suit::iter());
-    suit_isomorphic_representative.sort_by(|lhs, rhs| lhs.rank.cmp(&rhs.rank).reverse());
-
-    suit_isomorphism.insert(Suit::Hearts, suit_isomorphic_representative[0].suit);
-    unmapped_suits.remove(&suit_isomorphic_representative[0].suit);
-    suit_isomorphic_representative[0].suit = Suit::Hearts;
-
-    let lower_suit = if hand[0].suit == hand[1].suit {
-        Suit::Hearts
-    } else {
-        Suit::Diamonds
-    };
-    suit_isomorphism.insert(lower_suit, suit_isomorphic_representative[1].suit);
-    unmapped_suits.remove(&suit_isomorphic_representative[1].suit);
-    suit_isomorphic_representative[1].suit = lower_suit;
-
-    let mut unmapped_suits = unmapped_suits.into_iter();
-    for suit in Suit::iter() {
-        if suit_isomorphism.contains_key(&suit) {
-            continue
-        }
-        suit_isomorphism.insert(suit, unmapped_suits.next().context("no more suits to map in isomorphism")?);
-    }
+    let permutation = canonical_permutation(hand.iter().collect(), CardSet::EMPTY, CardSet::EMPTY);
+    let suit_isomorphic_representative = permutation.hand(hand);
+    let suit_isomorphism = permutation.inverse();
 
     let precalculated_solution = &solutions
         .iter()
-        .find(|s| s.my_hand.as_ref().eq(suit_isomorphic_representative.as_slice()))
+        .find(|s| s.my_hand.as_ref().eq(suit_isomorphic_representative.as_ref()))
         .with_context(|| format!("precalculated solution for hand {:?} not found", hand))?
         .solution;
 
     Ok(
         Solution{
             hands: precalculated_solution.hands.iter().map(|hand_solution| HandSolution{
-                hand: hand_solution.hand.iter().map(|card| Card{
-                    rank: card.rank,
-                    suit: suit_isomorphism[&card.suit],
-                }).collect_vec().into_boxed_slice(),
+                hand: hand_solution.hand.iter().map(|&card| suit_isomorphism.card(card)).collect_vec().into_boxed_slice(),
                 beats_me_count: hand_solution.beats_me_count,
                 is_beaten_count: hand_solution.is_beaten_count,
+                tie_count: precalculated_solution.board_possibilities - hand_solution.beats_me_count - hand_solution.is_beaten_count,
+                weight: hand_solution.weight,
+                beats_me_combination_counts: hand_solution.beats_me_combination_counts,
             }).collect_vec().into_boxed_slice(),
             board_possibilities: precalculated_solution.board_possibilities,
             win_count: precalculated_solution.win_count,
             lose_count: precalculated_solution.lose_count,
+            multiway: None,
+            approximation: None,
+            combination_counts: precalculated_solution.combination_counts,
         }
     )
 }
 
 pub fn full_deck() -> Box<[Card]> {
+    deck(Variant::Holdem)
+}
+
+/// All cards of the variant's deck, ordered by suit, then by rank.
+pub fn deck(variant: Variant) -> Box<[Card]> {
     Suit::iter()
-        .cartesian_product(Rank::iter())
+        .cartesian_product(Rank::iter().filter(|&rank| rank >= variant.lowest_rank()))
         .map(|(suit, rank)| Card { rank, suit })
         .collect_vec()
         .into_boxed_slice()
 }
 
-pub async fn solve_with_deck(
+pub async fn solve_with_deck(table: &Table, deck: &[Card]) -> Result<Solution> {
+    solve_with_deck_progress(table, deck, &mut ProgressReporter::new(None)).await
+}
+
+pub async fn solve_with_deck_progress(
     table: &Table,
     deck: &[Card],
-    cache: &mut HashMap<Box<[ReducedCard]>, u64>,
+    reporter: &mut ProgressReporter<'_>,
 ) -> Result<Solution> {
-    let mut yield_timer = YieldTimer::new(Duration::from_millis(50));
-    let mut used_cards = table.hand.to_vec();
-    used_cards.extend(table.board.iter().flatten());
-    let remaining_deck: Vec<_> = deck
-        .iter()
-        .cloned()
-        .filter(|card| !used_cards.contains(card))
-        .collect();
-    if deck.len() != remaining_deck.len() + used_cards.len() {
-        let mut used_cards_set: HashSet<_> = Default::default();
-        for &card in &used_cards {
-            if !used_cards_set.insert(card) {
-                bail!("card \"{card:#?}\" is used multiple times")
-            }
-        }
-        bail!(
-            "used cards \"{:#?}\" not from deck",
-            used_cards
-                .iter()
-                .filter(|c| !deck.contains(c))
-                .collect_vec()
-        );
-    }
-    let mut candidate_hands = vec![];
-    for (i, &card1) in remaining_deck[..remaining_deck.len() - 1]
-        .iter()
-        .enumerate()
-    {
-        for &card2 in &remaining_deck[i + 1..] {
-            let mut candidate_hand = vec![card1, card2];
-            candidate_hand.sort_by_key(|c| (Reverse(c.rank), c.suit));
-            candidate_hands.push(candidate_hand.into_boxed_slice())
+    let solution = solve_canonical(table, deck, None, reporter).await?;
+    Ok(solution.expect("enumeration without time budget is complete"))
+}
+
+/// Enumerates all runouts if that's projected to fit in the time budget. Otherwise, against a
+/// single opponent, candidate hands which already have all runouts evaluated keep their exact
+/// counts, the rest of the hands are sampled for the rest of the budget, and the solution has an
+/// approximation of the sampled part. With more opponents, all hands are sampled, because each
+/// runout is evaluated against all hands together.
+pub async fn solve_with_deck_time_budget(
+    table: &Table,
+    deck: &[Card],
+    time_budget: Duration,
+    reporter: &mut ProgressReporter<'_>,
+) -> Result<Solution> {
+    let start = Instant::now();
+    let table_cards = table_cards(table, deck)?;
+    let remaining_budget = || SamplingConfig {
+        time_budget: Some(time_budget.saturating_sub(start.elapsed())),
+        ..Default::default()
+    };
+    if table_cards.opponents > 1 {
+        if let Some(solution) = solve_canonical(table, deck, Some(time_budget), reporter).await? {
+            return Ok(solution);
         }
+        reporter.map_snapshots(SuitPermutation::IDENTITY);
+        return solve_sampled_progress(table, deck, &remaining_budget(), reporter).await;
     }
 
-    let choose = table.board.iter().filter(|x| x.is_none()).count();
-    let choose_from = remaining_deck.len() - 2;
-
-    let mut hands = Vec::with_capacity(candidate_hands.len());
-
-    let mut last_time = yield_timer.last;
-    for (i, candidate_hand) in candidate_hands.iter().enumerate() {
-        let current_time = yield_timer.yield_check().await;
-        if current_time.duration_since(last_time) >= Duration::from_millis(1000) {
-            log(format!("{}/{} hands evaluated", i, candidate_hands.len()).as_str());
-            last_time = current_time;
-        }
-        hands.push(
-            hand_solution(
-                &candidate_hand,
-                table,
-                &remaining_deck,
-                cache,
-                &mut yield_timer,
+    let Some(exact) = solve_hands_within(&table_cards, time_budget, reporter).await else {
+        return solve_sampled_progress(table, deck, &remaining_budget(), reporter).await;
+    };
+    let board_possibilities = n_choose_m(table_cards.remaining_deck.len() - 2, table_cards.choose);
+    if exact.done == exact.hands.len() {
+        return Ok(sorted_solution(
+            exact.hands,
+            board_possibilities,
+            None,
+            exact.combination_counts,
+        ));
+    }
+    let (exact_hands, sampled_hands) = exact.hands.split_at(exact.done);
+    let sampled_table_cards = TableCards {
+        hands: sampled_hands.to_vec(),
+        ..table_cards
+    };
+    let sampled = sample_table_cards(
+        &sampled_table_cards,
+        &remaining_budget(),
+        reporter,
+        |sampled| {
+            sampled.solution_with_exact_hands(
+                exact_hands,
+                board_possibilities,
+                exact.combination_counts,
             )
-            .await,
-        )
+        },
+    )
+    .await;
+    Ok(sampled.solution_with_exact_hands(
+        exact_hands,
+        board_possibilities,
+        exact.combination_counts,
+    ))
+}
+
+/// Candidate hands of a single opponent, the first `done` of them with exact counts.
+struct ExactHands {
+    hands: Vec<HandSolution>,
+    done: usize,
+    // of my final combinations over all runouts
+    combination_counts: [u64; COMBINATION_KIND_COUNT],
+}
+
+/// Evaluates all runouts of one candidate hand after another, while evaluating the next hand and
+/// sampling the hands after it is projected to fit in the time budget. My combination is evaluated
+/// once for each runout first, `None` when that alone is projected to exceed the time budget.
+async fn solve_hands_within(
+    table_cards: &TableCards,
+    time_budget: Duration,
+    reporter: &mut ProgressReporter<'_>,
+) -> Option<ExactHands> {
+    let start = Instant::now();
+    let mut yield_timer = YieldTimer::new(Duration::from_millis(50));
+    let &TableCards {
+        my_cards,
+        fixed_board_cards,
+        ref remaining_deck,
+        choose,
+        variant,
+        ..
+    } = table_cards;
+    let mut combination_counts = [0; COMBINATION_KIND_COUNT];
+    let runouts_count = n_choose_m(remaining_deck.len(), choose);
+    let mut runouts = Vec::new();
+    let mut fill_cards_map_i = Vec::from_iter(0..choose);
+    loop {
+        let elapsed = yield_timer.yield_check().await.duration_since(start);
+        // projection from the first few runouts is too noisy
+        if elapsed > time_budget
+            || (!runouts.is_empty()
+                && elapsed >= time_budget / 10
+                && elapsed.mul_f64(runouts_count as f64 / runouts.len() as f64) > time_budget)
+        {
+            return None;
+        }
+        let fill_cards: CardSet = fill_cards_map_i
+            .iter()
+            .map(|&i| remaining_deck[i])
+            .collect();
+        let my_combination =
+            best_combination_from_set(fixed_board_cards | fill_cards | my_cards, variant);
+        combination_counts[my_combination.kind() as usize] += 1;
+        runouts.push((fill_cards, my_combination.score_for(variant)));
+        if !next_combination(&mut fill_cards_map_i, remaining_deck.len()) {
+            break;
+        }
     }
+    let enumeration_time = start.elapsed();
+    // a sample evaluates my hand and the candidate hand on a runout
+    let sample_time = enumeration_time.mul_f64(2.0 / runouts_count as f64);
 
-    let score_fn = |hand: &HandSolution| hand.beats_me_count as i64 - hand.is_beaten_count as i64;
+    let mut hands = table_cards.hands.clone();
+    let hands_count = hands.len();
+    let mut done = 0;
+    for hand in &mut hands {
+        let elapsed = start.elapsed();
+        // before any hand is done, a hand is estimated by enumerating the runouts
+        let hand_time = match done {
+            0 => enumeration_time,
+            _ => (elapsed - enumeration_time) / done as u32,
+        };
+        let sampling_time =
+            sample_time.mul_f64(((hands_count - done - 1) as u64 * MIN_SAMPLES_PER_HAND) as f64);
+        if elapsed + hand_time + sampling_time > time_budget {
+            break;
+        }
+        reporter.report(done as u64, hands_count as u64, "hands evaluated");
+        let cards: CardSet = hand.hand.iter().collect();
+        for (i, &(fill_cards, my_score)) in runouts.iter().enumerate() {
+            // pre-flop, a single hand has over a million runouts
+            if i % 1024 == 0 {
+                yield_timer.yield_check().await;
+            }
+            if !cards.is_disjoint(fill_cards) {
+                continue;
+            }
+            let candidate =
+                best_combination_from_set(fixed_board_cards | fill_cards | cards, variant);
+            match my_score.cmp(&candidate.score_for(variant)) {
+                Ordering::Less => {
+                    hand.beats_me_count += 1;
+                    hand.beats_me_combination_counts[candidate.kind() as usize] += 1;
+                }
+                Ordering::Equal => hand.tie_count += 1,
+                Ordering::Greater => hand.is_beaten_count += 1,
+            }
+        }
+        done += 1;
+    }
+    if done == hands_count {
+        reporter.finish();
+    }
 
-    hands.sort_by_key(|hand| {
-        (
-            score_fn(hand),
-            // on same of the win/loss difference, fewer ties is better
-            // (just so the result doesn't look so fragmented - expected value is the same though)
-            hand.is_beaten_count,
-            hand.hand.clone(),
-        )
-    });
-    Ok(Solution {
-        board_possibilities: n_choose_m(choose_from, choose),
-        win_count: hands.partition_point(|hand| score_fn(hand) < 0) as u64,
-        lose_count: hands.len() as u64 - hands.partition_point(|hand| score_fn(hand) <= 0) as u64,
-        hands: hands.into(),
+    Some(ExactHands {
+        hands,
+        done,
+        combination_counts,
     })
 }
 
-fn n_choose_m(n: usize, m: usize) -> u64 {
-    ((n as u64 - m as u64 + 1u64)..=(n as u64)).product::<u64>()
-        / (1u64..=(m as u64)).product::<u64>()
+/// Solves the table with canonical suits, and maps the result back to the table's suits. `None`
+/// when enumerating all runouts would exceed the time budget.
+async fn solve_canonical(
+    table: &Table,
+    deck: &[Card],
+    time_budget: Option<Duration>,
+    reporter: &mut ProgressReporter<'_>,
+) -> Result<Option<Solution>> {
+    let table_cards = table_cards(table, deck)?;
+    let permutation = canonical_permutation(
+        table_cards.my_cards,
+        table_cards.fixed_board_cards,
+        table.dead.iter().collect(),
+    );
+    reporter.map_snapshots(permutation.inverse());
+    let solution = solve_table_cards_within(
+        &permutation.table_cards(&table_cards),
+        None,
+        reporter,
+        time_budget,
+    )
+    .await;
+    Ok(solution.map(|solution| permutation.inverse().solution(solution)))
 }
 
-async fn hand_solution(
-    candidate_hand: &[Card],
-    table: &Table,
-    remaining_deck: &[Card],
-    cache: &mut HashMap<Box<[ReducedCard]>, u64>,
-    yield_timer: &mut YieldTimer,
-) -> HandSolution {
-    let remaining_deck: Vec<_> = remaining_deck
-        .into_iter()
-        .cloned()
-        .filter(|card| !candidate_hand.contains(card))
-        .collect();
-    let fixed_board_cards = table.board.iter().cloned().flatten().collect_vec();
-    let fixed_cards_count = fixed_board_cards.len() + table.hand.len();
-    let mut beats_me_count = 0;
-    let mut is_beaten_count = 0;
-    let cards_to_fill = table.board.iter().filter(|x| x.is_none()).count();
-    let mut fill_cards_map_i = Vec::from_iter(0..cards_to_fill);
-
-    let mut my_final_cards: Vec<_> = fixed_board_cards
-        .iter()
-        .cloned()
-        .chain(table.hand.iter().cloned())
-        .chain(fill_cards_map_i.iter().map(|&i| remaining_deck[i]))
-        .collect();
-    let mut candidate_final_cards: Vec<_> = fixed_board_cards
-        .iter()
-        .cloned()
-        .chain(candidate_hand.iter().cloned())
-        .chain(fill_cards_map_i.iter().map(|&i| remaining_deck[i]))
-        .collect();
+/// Evaluates all runouts of the table, optionally recording the result of each runout.
+pub(crate) async fn solve_table_cards(
+    table_cards: &TableCards,
+    record: Option<&mut RunoutRecord>,
+    reporter: &mut ProgressReporter<'_>,
+) -> Solution {
+    solve_table_cards_within(table_cards, record, reporter, None)
+        .await
+        .expect("enumeration without time budget is complete")
+}
 
-    let mut i: u64 = 1;
-    'outer: loop {
-        const YIELD_EACH_N: u64 = 2000;
-        if i % YIELD_EACH_N == 0 {
-            yield_timer.yield_check().await;
+/// Like `solve_table_cards`, but gives up when evaluating all runouts is projected to exceed the
+/// time budget, or when the time budget runs out while evaluating a runout.
+async fn solve_table_cards_within(
+    table_cards: &TableCards,
+    mut record: Option<&mut RunoutRecord>,
+    reporter: &mut ProgressReporter<'_>,
+    time_budget: Option<Duration>,
+) -> Option<Solution> {
+    let start = Instant::now();
+    let mut yield_timer = YieldTimer::new(Duration::from_millis(50));
+    let remaining_deck = &table_cards.remaining_deck;
+    let mut evaluator = RunoutEvaluator::new(table_cards);
+    // recorded runouts have to be all evaluated
+    let mut symmetric_evaluator = record
+        .is_none()
+        .then(|| SymmetricEvaluator::new(table_cards));
+    if let Some(time_budget) = time_budget {
+        evaluator.set_deadline(start + time_budget);
+        if let Some(symmetric_evaluator) = symmetric_evaluator.as_mut() {
+            symmetric_evaluator.set_deadline(start + time_budget);
         }
-        i += 1;
+    }
+    let mut counts = RunoutCounts::new(table_cards);
 
-        let my_combination = best_combination(&my_final_cards, cache);
-        let candidate = best_combination(&candidate_final_cards, cache);
+    let runouts_count = n_choose_m(remaining_deck.len(), table_cards.choose);
+    let mut fill_cards_map_i = Vec::from_iter(0..table_cards.choose);
+    let mut runout_i = 0;
+    loop {
+        let now = yield_timer.yield_check().await;
+        if let Some(time_budget) = time_budget {
+            let elapsed = now.duration_since(start);
+            // projection from the first few runouts is too noisy
+            if runout_i > 0
+                && elapsed >= time_budget / 10
+                && elapsed.mul_f64(runouts_count as f64 / runout_i as f64) > time_budget
+            {
+                return None;
+            }
+        }
+        reporter.report(runout_i, runouts_count, "runouts evaluated");
+        if counts.runouts > 0 {
+            reporter.snapshot(|| counts.partial_solution(table_cards));
+        }
+        runout_i += 1;
 
-        match my_combination.cmp(&candidate) {
-            Ordering::Less => {
-                beats_me_count += 1;
+        let fill_cards: CardSet = fill_cards_map_i
+            .iter()
+            .map(|&i| remaining_deck[i])
+            .collect();
+        let deadline_exceeded = match symmetric_evaluator.as_mut() {
+            Some(symmetric_evaluator) => {
+                symmetric_evaluator.evaluate(fill_cards, &mut counts);
+                symmetric_evaluator.deadline_exceeded()
             }
-            Ordering::Equal => {}
-            Ordering::Greater => {
-                is_beaten_count += 1;
+            None => {
+                evaluator.evaluate(fill_cards, &mut counts, record.as_deref_mut());
+                evaluator.deadline_exceeded()
             }
+        };
+        if deadline_exceeded {
+            return None;
         }
 
-        if cards_to_fill == 0 {
+        if !next_combination(&mut fill_cards_map_i, remaining_deck.len()) {
             break;
         }
-        let mut fill_card_i = cards_to_fill - 1;
+    }
+    reporter.finish();
 
-        while fill_cards_map_i[fill_card_i] + cards_to_fill >= remaining_deck.len() + fill_card_i {
-            if fill_card_i == 0 {
-                break 'outer;
-            }
-            fill_card_i -= 1;
+    Some(counts.into_solution(table_cards))
+}
+
+/// Counts summed over evaluated runouts of a table.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunoutCounts {
+    pub(crate) runouts: u64,
+    pub(crate) hands: Vec<HandSolution>,
+    // index 0 counts wins, index i counts ties with i opponents
+    pub(crate) multiway_counts: Vec<u64>,
+    pub(crate) combination_counts: [u64; COMBINATION_KIND_COUNT],
+}
+
+impl RunoutCounts {
+    pub(crate) fn new(table_cards: &TableCards) -> Self {
+        RunoutCounts {
+            runouts: 0,
+            hands: table_cards.hands.clone(),
+            multiway_counts: vec![0; table_cards.opponents + 1],
+            combination_counts: [0; COMBINATION_KIND_COUNT],
         }
+    }
 
-        fill_cards_map_i[fill_card_i] += 1;
-        my_final_cards[fixed_cards_count + fill_card_i] =
-            remaining_deck[fill_cards_map_i[fill_card_i]];
-        candidate_final_cards[fixed_cards_count + fill_card_i] =
-            remaining_deck[fill_cards_map_i[fill_card_i]];
-        fill_card_i += 1;
+    /// Adds counts of other runouts of the same table.
+    pub(crate) fn merge(mut self, other: RunoutCounts) -> Self {
+        self.runouts += other.runouts;
+        for (hand, other_hand) in self.hands.iter_mut().zip(other.hands) {
+            hand.beats_me_count += other_hand.beats_me_count;
+            hand.is_beaten_count += other_hand.is_beaten_count;
+            hand.tie_count += other_hand.tie_count;
+            for (count, other_count) in hand
+                .beats_me_combination_counts
+                .iter_mut()
+                .zip(other_hand.beats_me_combination_counts)
+            {
+                *count += other_count;
+            }
+        }
+        for (count, other_count) in self.multiway_counts.iter_mut().zip(other.multiway_counts) {
+            *count += other_count;
+        }
+        for (count, other_count) in self
+            .combination_counts
+            .iter_mut()
+            .zip(other.combination_counts)
+        {
+            *count += other_count;
+        }
+        self
+    }
 
-        while fill_card_i < cards_to_fill {
-            fill_cards_map_i[fill_card_i] = fill_cards_map_i[fill_card_i - 1] + 1;
-            my_final_cards[fixed_cards_count + fill_card_i] =
-                remaining_deck[fill_cards_map_i[fill_card_i]];
-            candidate_final_cards[fixed_cards_count + fill_card_i] =
-                remaining_deck[fill_cards_map_i[fill_card_i]];
-            fill_card_i += 1;
+    /// Provisional solution of the runouts counted so far, with counts of each hand scaled up to
+    /// all runouts the hand is possible on.
+    pub(crate) fn partial_solution(&self, table_cards: &TableCards) -> Solution {
+        let remaining_cards = table_cards.remaining_deck.len();
+        let board_possibilities = n_choose_m(remaining_cards - 2, table_cards.choose);
+        let mut counts = self.clone();
+        for hand in &mut counts.hands {
+            let counted = hand.beats_me_count + hand.is_beaten_count + hand.tie_count;
+            let beats_me_count = hand.beats_me_count;
+            let mut outcome_counts = [hand.beats_me_count, hand.is_beaten_count, hand.tie_count];
+            scale_counts(&mut outcome_counts, counted, board_possibilities);
+            [hand.beats_me_count, hand.is_beaten_count, hand.tie_count] = outcome_counts;
+            scale_counts(
+                &mut hand.beats_me_combination_counts,
+                beats_me_count,
+                hand.beats_me_count,
+            );
         }
+        let runouts = n_choose_m(remaining_cards, table_cards.choose);
+        scale_counts(&mut counts.combination_counts, self.runouts, runouts);
+        counts.into_solution(table_cards)
+    }
+
+    pub(crate) fn into_solution(self, table_cards: &TableCards) -> Solution {
+        let &TableCards {
+            ref remaining_deck,
+            choose,
+            opponents,
+            ..
+        } = table_cards;
+        let multiway = (opponents > 1).then(|| {
+            let possibilities = self.runouts
+                * opponents_hands_possibilities(remaining_deck.len() - choose, opponents);
+            multiway_solution(possibilities, &self.multiway_counts)
+        });
+        sorted_solution(
+            self.hands,
+            n_choose_m(remaining_deck.len() - 2, choose),
+            multiway,
+            self.combination_counts,
+        )
     }
+}
 
-    HandSolution {
-        hand: candidate_hand.into(),
-        beats_me_count,
-        is_beaten_count,
+// scales counts adding up to `counted` so that they add up to `total`, cumulative sums are rounded
+// so that rounding errors don't add up
+pub(crate) fn scale_counts(counts: &mut [u64], counted: u64, total: u64) {
+    if counted == 0 {
+        return;
+    }
+    let mut cumulative = 0;
+    let mut scaled_cumulative = 0;
+    for count in counts {
+        cumulative += *count;
+        let scaled = (cumulative as f64 * total as f64 / counted as f64).round() as u64;
+        *count = scaled - scaled_cumulative;
+        scaled_cumulative = scaled;
     }
 }
 
-fn best_combination(cards: &[Card], cache: &mut HashMap<Box<[ReducedCard]>, u64>) -> u64 {
-    let reduced = reduce_card_set(cards);
-    *cache
-        .entry(reduced)
-        .or_insert_with_key(|reduced| best_combination_from_sorted(&reduced).score())
+/// Evaluates single runouts of a table, reusing its buffers between runouts.
+pub(crate) struct RunoutEvaluator<'a> {
+    table_cards: &'a TableCards,
+    candidate_cards: Vec<CardSet>,
+    not_beating_hands: Vec<(CardSet, bool)>,
+    runout_multiway_counts: Vec<u64>,
+    deadline: Deadline,
 }
 
-fn reduce_card_set(cards: &[Card]) -> Box<[ReducedCard]> {
-    let mut suits_counts = [0; SUIT_COUNT];
-    for card in cards {
-        suits_counts[card.suit as usize] += 1
+impl<'a> RunoutEvaluator<'a> {
+    pub(crate) fn new(table_cards: &'a TableCards) -> Self {
+        let candidate_cards = table_cards
+            .hands
+            .iter()
+            .map(|hand| hand.hand.iter().collect::<CardSet>())
+            .collect_vec();
+        RunoutEvaluator {
+            table_cards,
+            not_beating_hands: Vec::with_capacity(candidate_cards.len()),
+            candidate_cards,
+            runout_multiway_counts: vec![0; table_cards.opponents + 1],
+            deadline: Deadline::default(),
+        }
     }
-    let flush_suit = Suit::iter().find(|&suit| suits_counts[suit as usize] >= COMBINATION_SIZE);
-    let mut reduced_set = Vec::with_capacity(cards.len());
-    for card in cards {
-        reduced_set.push(ReducedCard {
-            is_flush: flush_suit
-                .map(|flush_suit| card.suit == flush_suit)
-                .unwrap_or(false),
-            rank: card.rank,
-        })
+
+    /// Stops counting sets of opponents' hands at the deadline, a single runout with many
+    /// opponents can take longer than the whole time budget.
+    pub(crate) fn set_deadline(&mut self, deadline: Instant) {
+        self.deadline.at = Some(deadline);
     }
-    reduced_set.sort_by(|lhs, rhs| lhs.cmp(rhs).reverse());
-    reduced_set.into_boxed_slice()
-}
 
-fn best_combination_from_sorted(cards_descending: &[ReducedCard]) -> Combination {
-    let mut flush_end_i = 0;
-    while flush_end_i < cards_descending.len() && cards_descending[flush_end_i].is_flush {
-        flush_end_i += 1
+    /// Whether the deadline passed, and the counts of the last runout are incomplete.
+    pub(crate) fn deadline_exceeded(&self) -> bool {
+        self.deadline.exceeded
     }
-    let suited_cards = &cards_descending[0..flush_end_i];
-    if let Some(rank) = find_straight_highest_rank(suited_cards) {
-        return Combination::StraightFlush(rank);
+
+    /// Evaluates the runout once for my hand, and then against every candidate hand that doesn't
+    /// use any of the runout cards.
+    pub(crate) fn evaluate(
+        &mut self,
+        fill_cards: CardSet,
+        counts: &mut RunoutCounts,
+        mut record: Option<&mut RunoutRecord>,
+    ) {
+        let &TableCards {
+            my_cards,
+            fixed_board_cards,
+            opponents,
+            variant,
+            ..
+        } = self.table_cards;
+        counts.runouts += 1;
+        let board_cards = fixed_board_cards | fill_cards;
+        let my_combination = best_combination_from_set(board_cards | my_cards, variant);
+        counts.combination_counts[my_combination.kind() as usize] += 1;
+        if let Some(record) = record.as_mut() {
+            record.push_runout(fill_cards, my_combination.kind());
+        }
+        let my_combination = my_combination.score_for(variant);
+        self.not_beating_hands.clear();
+        for (hand, &cards) in counts.hands.iter_mut().zip(&self.candidate_cards) {
+            if !cards.is_disjoint(fill_cards) {
+                if let Some(record) = record.as_mut() {
+                    record.push_outcome(Outcome::Impossible);
+                }
+                continue;
+            }
+            let candidate = best_combination_from_set(board_cards | cards, variant);
+            let outcome = match my_combination.cmp(&candidate.score_for(variant)) {
+                Ordering::Less => {
+                    hand.beats_me_count += 1;
+                    hand.beats_me_combination_counts[candidate.kind() as usize] += 1;
+                    Outcome::Lose(candidate.kind())
+                }
+                Ordering::Equal => {
+                    hand.tie_count += 1;
+                    self.not_beating_hands.push((cards, true));
+                    Outcome::Tie
+                }
+                Ordering::Greater => {
+                    hand.is_beaten_count += 1;
+                    self.not_beating_hands.push((cards, false));
+                    Outcome::Win
+                }
+            };
+            if let Some(record) = record.as_mut() {
+                record.push_outcome(outcome);
+            }
+        }
+        if opponents > 1 {
+            self.runout_multiway_counts.fill(0);
+            count_opponents_hands(
+                &self.not_beating_hands,
+                CardSet::EMPTY,
+                opponents,
+                0,
+                &mut self.runout_multiway_counts,
+                &mut self.deadline,
+            );
+            for (count, runout_count) in counts
+                .multiway_counts
+                .iter_mut()
+                .zip(&self.runout_multiway_counts)
+            {
+                *count += runout_count;
+            }
+            if let Some(record) = record.as_mut() {
+                record.push_multiway_counts(&self.runout_multiway_counts);
+            }
+        }
     }
-    let mut ranks_counts = [0; RANK_COUNT];
-    for card in cards_descending {
-        ranks_counts[card.rank as usize] += 1
+}
+
+/// Validated cards of a table, with opponent's candidate hands that are still possible.
+pub(crate) struct TableCards {
+    pub(crate) my_cards: CardSet,
+    pub(crate) fixed_board_cards: CardSet,
+    // deck without my hand, fixed board cards and dead cards, in deck order
+    pub(crate) remaining_deck: Vec<Card>,
+    // count of board cards still to be dealt
+    pub(crate) choose: usize,
+    pub(crate) opponents: usize,
+    // candidate hands with all counts zero
+    pub(crate) hands: Vec<HandSolution>,
+    pub(crate) variant: Variant,
+}
+
+pub(crate) fn table_cards(table: &Table, deck: &[Card]) -> Result<TableCards> {
+    if !table.known_hands.is_empty() {
+        bail!("table with known opponents' hands is solved as a showdown")
     }
-    let mut same_of_a_kind: Vec<_> = Rank::iter()
-        .map(|rank| (ranks_counts[rank as usize], rank))
+    let deck_set: CardSet = deck.iter().collect();
+    let used_cards = check_used_cards(
+        table
+            .hand
+            .iter()
+            .chain(table.board.iter().flatten())
+            .chain(table.dead.iter()),
+        deck_set,
+    )?;
+    let remaining_deck: Vec<_> = deck
+        .iter()
+        .cloned()
+        .filter(|&card| !used_cards.contains(card))
         .collect();
-    same_of_a_kind.sort_by_key(|&x| Reverse(x));
-    if same_of_a_kind[0].0 == 4 {
-        return Combination::FourOfAKind([same_of_a_kind[0].1, same_of_a_kind[1].1]);
-    }
-    if same_of_a_kind[0].0 == 3 && same_of_a_kind[1].0 == 2 {
-        return Combination::FullHouse([same_of_a_kind[0].1, same_of_a_kind[1].1]);
-    }
-    if suited_cards.len() > 0 {
-        return Combination::Flush([
-            suited_cards[0].rank,
-            suited_cards[1].rank,
-            suited_cards[2].rank,
-            suited_cards[3].rank,
-            suited_cards[4].rank,
-        ]);
-    }
-    if let Some(rank) = find_straight_highest_rank(cards_descending) {
-        return Combination::Straight(rank);
+    let choose = table.board.iter().filter(|x| x.is_none()).count();
+    if table.players < 2 {
+        bail!("at least 2 players are needed, got {}", table.players)
     }
-    if same_of_a_kind[0].0 == 3 {
-        return Combination::ThreeOfAKind([
-            same_of_a_kind[0].1,
-            same_of_a_kind[1].1,
-            same_of_a_kind[2].1,
-        ]);
-    }
-    if same_of_a_kind[0].0 == 2 && same_of_a_kind[1].0 == 2 {
-        return Combination::TwoPairs([
-            same_of_a_kind[0].1,
-            same_of_a_kind[1].1,
-            same_of_a_kind[2].1,
-        ]);
-    }
-    if same_of_a_kind[0].0 == 2 {
-        return Combination::Pair([
-            same_of_a_kind[0].1,
-            same_of_a_kind[1].1,
-            same_of_a_kind[2].1,
-            same_of_a_kind[3].1,
-        ]);
-    }
-    Combination::HighCard([
-        cards_descending[0].rank,
-        cards_descending[1].rank,
-        cards_descending[2].rank,
-        cards_descending[3].rank,
-        cards_descending[4].rank,
-    ])
-}
-
-fn find_straight_highest_rank(cards_descending: &[ReducedCard]) -> Option<Rank> {
-    if cards_descending.len() < COMBINATION_SIZE {
-        return None;
-    }
-    let mut last_rank = cards_descending[cards_descending.len() - 1].rank;
-    let mut run_count = 1;
-    let mut straight_highest = None;
-    for card in cards_descending[0..cards_descending.len() - 1].iter().rev() {
-        if last_rank == card.rank {
-            continue;
-        }
-        if last_rank as u32 + 1 == card.rank as u32 {
-            run_count += 1;
-            if run_count >= COMBINATION_SIZE {
-                straight_highest = Some(card.rank)
+    let opponents = table.players - 1;
+    if remaining_deck.len() < choose + 2 * opponents {
+        bail!(
+            "not enough cards in deck for {} players, {} cards remain",
+            table.players,
+            remaining_deck.len()
+        )
+    }
+    let full_range;
+    let opponent_range = match &table.opponent_range {
+        Some(range) => {
+            if opponents > 1 {
+                bail!("opponent range is supported only with a single opponent")
             }
-        } else {
-            run_count = 1;
+            range
         }
-        last_rank = card.rank;
+        None => {
+            full_range = Range::full(&remaining_deck);
+            &full_range
+        }
+    };
+    let hands = opponent_range
+        .without_cards(!deck_set | used_cards)
+        .map(|weighted| HandSolution {
+            hand: weighted.hand.into(),
+            beats_me_count: 0,
+            is_beaten_count: 0,
+            tie_count: 0,
+            weight: weighted.weight,
+            beats_me_combination_counts: [0; COMBINATION_KIND_COUNT],
+        })
+        .collect_vec();
+    if hands.is_empty() {
+        bail!("no hand from opponent's range is possible")
+    }
+
+    Ok(TableCards {
+        my_cards: table.hand.iter().collect(),
+        fixed_board_cards: table.board.iter().flatten().collect(),
+        remaining_deck,
+        choose,
+        opponents,
+        hands,
+        variant: table.variant,
+    })
+}
+
+/// `counts[0]` is the count of wins, `counts[i]` the count of ties with `i` opponents.
+pub(crate) fn multiway_solution(possibilities: u64, counts: &[u64]) -> MultiwaySolution {
+    let win_count = counts[0];
+    let tie_counts: Box<[u64]> = counts[1..].into();
+    MultiwaySolution {
+        opponents: tie_counts.len() as u64,
+        possibilities,
+        win_count,
+        lose_count: possibilities - win_count - tie_counts.iter().sum::<u64>(),
+        tie_counts,
     }
-    straight_highest
 }
 
-pub struct YieldTimer {
-    last: Instant,
-    interval: Duration,
+pub(crate) fn sorted_solution(
+    mut hands: Vec<HandSolution>,
+    board_possibilities: u64,
+    multiway: Option<MultiwaySolution>,
+    combination_counts: [u64; COMBINATION_KIND_COUNT],
+) -> Solution {
+    sort_hands(&mut hands);
+    Solution {
+        multiway,
+        approximation: None,
+        combination_counts: Some(combination_counts),
+        board_possibilities,
+        win_count: hands.partition_point(|hand| hand_score(hand) < 0) as u64,
+        lose_count: hands.len() as u64 - hands.partition_point(|hand| hand_score(hand) <= 0) as u64,
+        hands: hands.into(),
+    }
 }
 
-impl YieldTimer {
-    pub fn new(interval: Duration) -> Self {
-        Self {
-            last: Instant::now(),
-            interval,
-        }
+fn hand_score(hand: &HandSolution) -> i64 {
+    hand.beats_me_count as i64 - hand.is_beaten_count as i64
+}
+
+/// Sorts candidate hands from the ones I beat the most to the ones beating me the most.
+pub(crate) fn sort_hands(hands: &mut [HandSolution]) {
+    hands.sort_by_key(|hand| {
+        (
+            hand_score(hand),
+            // on same of the win/loss difference, fewer ties is better
+            // (just so the result doesn't look so fragmented - expected value is the same though)
+            hand.is_beaten_count,
+            hand.hand.clone(),
+        )
+    });
+}
+
+/// Solves equity of my range against opponent's range. Each of my hands is evaluated against all
+/// opponent's hands that don't share cards with it, on all board completions.
+pub async fn solve_range_vs_range(
+    my_range: &Range,
+    opponent_range: &Range,
+    board: &[Option<Card>],
+    deck: &[Card],
+    variant: Variant,
+) -> Result<RangeSolution> {
+    let mut yield_timer = YieldTimer::new(Duration::from_millis(50));
+    let deck_set: CardSet = deck.iter().collect();
+    let fixed_board_cards = check_used_cards(board.iter().flatten(), deck_set)?;
+    let remaining_deck: Vec<_> = deck
+        .iter()
+        .cloned()
+        .filter(|&card| !fixed_board_cards.contains(card))
+        .collect();
+    let choose = board.iter().filter(|x| x.is_none()).count();
+    if remaining_deck.len() < choose + 4 {
+        bail!(
+            "not enough cards in deck, {} cards remain",
+            remaining_deck.len()
+        )
     }
 
-    pub async fn yield_check(&mut self) -> Instant {
-        let now = Instant::now();
-        if now.duration_since(self.last) >= self.interval {
-            // use sleep(0) instead of async_std::task::yield_now, as that still causes frozen UI in javascript
-            task::sleep(Duration::from_millis(0)).await;
-            self.last = now;
+    let unavailable_cards = !deck_set | fixed_board_cards;
+    let mut hands = my_range
+        .without_cards(unavailable_cards)
+        .map(|weighted| RangeHandSolution {
+            hand: weighted.hand.into(),
+            weight: weighted.weight,
+            win_count: 0.0,
+            tie_count: 0.0,
+            lose_count: 0.0,
+        })
+        .collect_vec();
+    let opponent_hands = opponent_range
+        .without_cards(unavailable_cards)
+        .collect_vec();
+    if hands.is_empty() {
+        bail!("no hand from my range is possible")
+    }
+    if opponent_hands.is_empty() {
+        bail!("no hand from opponent's range is possible")
+    }
+    let my_cards = hands
+        .iter()
+        .map(|hand| hand.hand.iter().collect::<CardSet>())
+        .collect_vec();
+    let opponent_cards = opponent_hands
+        .iter()
+        .map(|weighted| weighted.hand.iter().collect::<CardSet>())
+        .collect_vec();
+    let mut opponent_scores = Vec::with_capacity(opponent_hands.len());
+
+    let mut fill_cards_map_i = Vec::from_iter(0..choose);
+    loop {
+        yield_timer.yield_check().await;
+
+        let fill_cards: CardSet = fill_cards_map_i
+            .iter()
+            .map(|&i| remaining_deck[i])
+            .collect();
+        let board_cards = fixed_board_cards | fill_cards;
+        opponent_scores.clear();
+        opponent_scores.extend(
+            opponent_cards
+                .iter()
+                .zip(&opponent_hands)
+                .filter(|(&cards, _)| cards.is_disjoint(fill_cards))
+                .map(|(&cards, weighted)| {
+                    (
+                        cards,
+                        weighted.weight,
+                        best_combination(board_cards | cards, variant),
+                    )
+                }),
+        );
+        for (hand, &cards) in hands.iter_mut().zip(&my_cards) {
+            if !cards.is_disjoint(fill_cards) {
+                continue;
+            }
+            let my_combination = best_combination(board_cards | cards, variant);
+            for &(opponent_cards, weight, opponent_combination) in &opponent_scores {
+                if !cards.is_disjoint(opponent_cards) {
+                    continue;
+                }
+                match my_combination.cmp(&opponent_combination) {
+                    Ordering::Less => hand.lose_count += weight,
+                    Ordering::Equal => hand.tie_count += weight,
+                    Ordering::Greater => hand.win_count += weight,
+                }
+            }
+        }
+
+        if !next_combination(&mut fill_cards_map_i, remaining_deck.len()) {
+            break;
         }
-        now
     }
+
+    let (my_shares, total) = hands.iter().fold((0.0, 0.0), |(shares, total), hand| {
+        (
+            shares + hand.weight * (hand.win_count + hand.tie_count / 2.0),
+            total + hand.weight * (hand.win_count + hand.tie_count + hand.lose_count),
+        )
+    });
+    if total == 0.0 {
+        bail!("no hand from my range can be played against opponent's range")
+    }
+    let my_equity = my_shares / total;
+    Ok(RangeSolution {
+        hands: hands.into(),
+        my_equity,
+        opponent_equity: 1.0 - my_equity,
+    })
 }
 
-#[cfg(test)]
+/// Checks that no card is used multiple times and all cards are from the deck, returns the cards.
+pub(crate) fn check_used_cards<'a>(
+    cards: impl Iterator<Item = &'a Card>,
+    deck_set: CardSet,
+) -> Result<CardSet> {
+    let mut used_cards = CardSet::EMPTY;
+    for &card in cards {
+        if !used_cards.insert(card) {
+            bail!("card \"{card:#?}\" is used multiple times")
+        }
+    }
+    if !used_cards.is_subset(deck_set) {
+        bail!(
+            "used cards \"{:#?}\" not from deck",
+            (used_cards - deck_set).iter().collect_vec()
+        );
+    }
+    Ok(used_cards)
+}
+
+pub(crate) fn n_choose_m(n: usize, m: usize) -> u64 {
+    ((n as u64 - m as u64 + 1u64)..=(n as u64)).product::<u64>()
+        / (1u64..=(m as u64)).product::<u64>()
+}
+
+/// Counts all sets of `opponents` hands with no shared cards, such that none of the hands beats
+/// me. `counts[i]` is incremented for each set where exactly `i` of the hands tie with me.
+fn count_opponents_hands(
+    not_beating_hands: &[(CardSet, bool)],
+    used_cards: CardSet,
+    opponents: usize,
+    ties: usize,
+    counts: &mut [u64],
+    deadline: &mut Deadline,
+) {
+    if deadline.is_exceeded() {
+        return;
+    }
+    if opponents == 0 {
+        counts[ties] += 1;
+        return;
+    }
+    for (i, &(cards, is_tie)) in not_beating_hands.iter().enumerate() {
+        if !cards.is_disjoint(used_cards) {
+            continue;
+        }
+        count_opponents_hands(
+            &not_beating_hands[i + 1..],
+            used_cards | cards,
+            opponents - 1,
+            ties + is_tie as usize,
+            counts,
+            deadline,
+        );
+    }
+}
+
+#[derive(Default)]
+struct Deadline {
+    at: Option<Instant>,
+    checks: u32,
+    exceeded: bool,
+}
+
+impl Deadline {
+    fn is_exceeded(&mut self) -> bool {
+        if let Some(at) = self.at {
+            self.checks = self.checks.wrapping_add(1);
+            // reading the clock on every check would take longer than the counting
+            if self.checks.is_multiple_of(4096) && Instant::now() >= at {
+                self.exceeded = true;
+            }
+        }
+        self.exceeded
+    }
+}
+
+/// Number of ways to deal 2-card hands to `opponents` (indistinguishable) players from `n` cards.
+pub(crate) fn opponents_hands_possibilities(n: usize, opponents: usize) -> u64 {
+    (0..opponents)
+        .map(|i| n_choose_m(n - 2 * i, 2))
+        .product::<u64>()
+        / (1..=opponents as u64).product::<u64>()
+}
+
+/// Advances `indices` (strictly increasing indices into a slice of length `n`) to the next
+/// combination in lexicographic order. Returns false if there is no next combination.
+pub(crate) fn next_combination(indices: &mut [usize], n: usize) -> bool {
+    let k = indices.len();
+    let mut i = k;
+    while i > 0 {
+        i -= 1;
+        if indices[i] + k < n + i {
+            indices[i] += 1;
+            for j in i + 1..k {
+                indices[j] = indices[j - 1] + 1;
+            }
+            return true;
+        }
+    }
+    false
+}
+
+/// Score of the best combination of the cards, see `Combination::score_for`.
+pub fn best_combination(cards: CardSet, variant: Variant) -> u64 {
+    best_combination_from_set(cards, variant).score_for(variant)
+}
+
+pub(crate) fn best_combination_from_set(cards: CardSet, variant: Variant) -> Combination {
+    let suit_masks = Suit::ALL.map(|suit| cards.suit_ranks(suit));
+    let flush_mask = suit_masks
+        .iter()
+        .copied()
+        .find(|mask| mask.count_ones() as usize >= COMBINATION_SIZE);
+    if let Some(rank) = flush_mask.and_then(|mask| find_straight_highest_rank(mask, variant)) {
+        return Combination::StraightFlush(rank);
+    }
+    let short_deck = matches!(variant, Variant::ShortDeck { .. });
+
+    // same_of_a_kind[n] is a mask of ranks present exactly n times, counted by adding the suit
+    // masks as bit-sliced numbers (count_bits[b] holds bit b of each rank's count)
+    let mut count_bits = [0u16; 3];
+    for mask in suit_masks {
+        let carry0 = count_bits[0] & mask;
+        let carry1 = count_bits[1] & carry0;
+        count_bits[0] ^= mask;
+        count_bits[1] ^= carry0;
+        count_bits[2] |= carry1;
+    }
+    let [bit0, bit1, bit2] = count_bits;
+    let same_of_a_kind = [0, bit0 & !bit1, !bit0 & bit1, bit0 & bit1, bit2];
+    let all_ranks = suit_masks.iter().fold(0, |acc, mask| acc | mask);
+
+    if same_of_a_kind[4] != 0 {
+        let [four] = highest_ranks(same_of_a_kind[4]);
+        let [kicker] = highest_ranks(all_ranks & !rank_bit(four));
+        return Combination::FourOfAKind([four, kicker]);
+    }
+    // flush beats full house in short deck
+    if let Some(mask) = flush_mask.filter(|_| short_deck) {
+        return Combination::Flush(highest_ranks(mask));
+    }
+    if same_of_a_kind[3] != 0 {
+        let [three] = highest_ranks(same_of_a_kind[3]);
+        // second three of a kind can be used as the pair
+        let pairs = (same_of_a_kind[3] & !rank_bit(three)) | same_of_a_kind[2];
+        if pairs != 0 {
+            let [two] = highest_ranks(pairs);
+            return Combination::FullHouse([three, two]);
+        }
+    }
+    if let Some(mask) = flush_mask {
+        return Combination::Flush(highest_ranks(mask));
+    }
+    let three_of_a_kind = || {
+        let [three] = highest_ranks(same_of_a_kind[3]);
+        let [kicker1, kicker2] = highest_ranks(all_ranks & !rank_bit(three));
+        Combination::ThreeOfAKind([three, kicker1, kicker2])
+    };
+    let trips_beat_straight = variant
+        == Variant::ShortDeck {
+            trips_beat_straight: true,
+        };
+    if trips_beat_straight && same_of_a_kind[3] != 0 {
+        return three_of_a_kind();
+    }
+    if let Some(rank) = find_straight_highest_rank(all_ranks, variant) {
+        return Combination::Straight(rank);
+    }
+    if same_of_a_kind[3] != 0 {
+        return three_of_a_kind();
+    }
+    if same_of_a_kind[2].count_ones() >= 2 {
+        let [pair1, pair2] = highest_ranks(same_of_a_kind[2]);
+        let [kicker] = highest_ranks(all_ranks & !rank_bit(pair1) & !rank_bit(pair2));
+        return Combination::TwoPairs([pair1, pair2, kicker]);
+    }
+    if same_of_a_kind[2] != 0 {
+        let [pair] = highest_ranks(same_of_a_kind[2]);
+        let [kicker1, kicker2, kicker3] = highest_ranks(all_ranks & !rank_bit(pair));
+        return Combination::Pair([pair, kicker1, kicker2, kicker3]);
+    }
+    Combination::HighCard(highest_ranks(all_ranks))
+}
+
+fn rank_bit(rank: Rank) -> u16 {
+    1 << rank as u16
+}
+
+/// Takes `N` highest ranks from the rank mask, missing ranks (for masks with less than `N` ranks)
+/// are filled with the lowest rank.
+fn highest_ranks<const N: usize>(mut rank_mask: u16) -> [Rank; N] {
+    let mut ranks = [Rank::default(); N];
+    for rank in ranks.iter_mut() {
+        if rank_mask == 0 {
+            break;
+        }
+        let rank_i = (u16::BITS - 1 - rank_mask.leading_zeros()) as usize;
+        *rank = Rank::ALL[rank_i];
+        rank_mask &= !(1 << rank_i);
+    }
+    ranks
+}
+
+const RANK_MASKS_COUNT: usize = 1 << RANK_COUNT;
+
+// For each possible rank mask, 1 + index of the highest rank of a straight in that mask, or 0 if
+// there is no straight.
+static STRAIGHT_HIGHEST_RANKS: [u8; RANK_MASKS_COUNT] = straight_highest_ranks_table();
+
+const fn straight_highest_ranks_table() -> [u8; RANK_MASKS_COUNT] {
+    const STRAIGHT_MASK: usize = (1 << COMBINATION_SIZE) - 1;
+    // ace can also play as the lowest card, completing A-2-3-4-5 ("wheel")
+    const WHEEL_MASK: usize = (STRAIGHT_MASK >> 1) | (1 << (RANK_COUNT - 1));
+    let mut table = [0; RANK_MASKS_COUNT];
+    let mut rank_mask = 0;
+    while rank_mask < RANK_MASKS_COUNT {
+        let mut lowest = RANK_COUNT - COMBINATION_SIZE + 1;
+        while lowest > 0 {
+            lowest -= 1;
+            if rank_mask & (STRAIGHT_MASK << lowest) == STRAIGHT_MASK << lowest {
+                table[rank_mask] = (lowest + COMBINATION_SIZE) as u8;
+                break;
+            }
+        }
+        if table[rank_mask] == 0 && rank_mask & WHEEL_MASK == WHEEL_MASK {
+            table[rank_mask] = COMBINATION_SIZE as u8 - 1;
+        }
+        rank_mask += 1;
+    }
+    table
+}
+
+// ace can also play as the lowest card in short deck, completing A-6-7-8-9
+const SHORT_DECK_WHEEL_MASK: u16 = (0b1111 << Rank::N6 as u16) | (1 << Rank::A as u16);
+
+fn find_straight_highest_rank(rank_mask: u16, variant: Variant) -> Option<Rank> {
+    match STRAIGHT_HIGHEST_RANKS[rank_mask as usize] {
+        0 if matches!(variant, Variant::ShortDeck { .. })
+            && rank_mask & SHORT_DECK_WHEEL_MASK == SHORT_DECK_WHEEL_MASK =>
+        {
+            Some(Rank::N9)
+        }
+        0 => None,
+        rank_i => Some(Rank::ALL[rank_i as usize - 1]),
+    }
+}
+
+pub struct YieldTimer {
+    last: Instant,
+    interval: Duration,
+}
+
+impl YieldTimer {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            last: Instant::now(),
+            interval,
+        }
+    }
+
+    pub async fn yield_check(&mut self) -> Instant {
+        let now = Instant::now();
+        if now.duration_since(self.last) >= self.interval {
+            // use sleep(0) instead of async_std::task::yield_now, as that still causes frozen UI in javascript
+            task::sleep(Duration::from_millis(0)).await;
+            self.last = now;
+        }
+        now
+    }
+}
+
+#[cfg(test)]
 mod tests {
     use super::*;
+    use crate::types::CombinationKind;
     use futures::executor::block_on;
     use rstest::rstest;
     use std::cmp::Ordering;
@@ -551,12 +1466,127 @@ mod tests {
     ], Combination::StraightFlush(
         Rank::N6,
     ))]
+    #[case(vec![
+        Card{rank: Rank::A,suit: Suit::Hearts},
+        Card{rank: Rank::N2,suit: Suit::Spades},
+        Card{rank: Rank::N3,suit: Suit::Clubs},
+        Card{rank: Rank::N4,suit: Suit::Diamonds},
+        Card{rank: Rank::N5,suit: Suit::Hearts},
+        Card{rank: Rank::K,suit: Suit::Diamonds},
+        Card{rank: Rank::N9,suit: Suit::Clubs},
+    ], Combination::Straight(
+        Rank::N5,
+    ))]
+    #[case(vec![
+        Card{rank: Rank::A,suit: Suit::Hearts},
+        Card{rank: Rank::N2,suit: Suit::Spades},
+        Card{rank: Rank::N3,suit: Suit::Clubs},
+        Card{rank: Rank::N4,suit: Suit::Diamonds},
+        Card{rank: Rank::N5,suit: Suit::Hearts},
+        Card{rank: Rank::N6,suit: Suit::Diamonds},
+        Card{rank: Rank::N9,suit: Suit::Clubs},
+    ], Combination::Straight(
+        Rank::N6,
+    ))]
+    #[case(vec![
+        Card{rank: Rank::A,suit: Suit::Clubs},
+        Card{rank: Rank::N2,suit: Suit::Clubs},
+        Card{rank: Rank::N3,suit: Suit::Clubs},
+        Card{rank: Rank::N4,suit: Suit::Clubs},
+        Card{rank: Rank::N5,suit: Suit::Clubs},
+        Card{rank: Rank::K,suit: Suit::Clubs},
+        Card{rank: Rank::A,suit: Suit::Diamonds},
+    ], Combination::StraightFlush(
+        Rank::N5,
+    ))]
+    #[case(vec![
+        Card{rank: Rank::A,suit: Suit::Clubs},
+        Card{rank: Rank::N2,suit: Suit::Clubs},
+        Card{rank: Rank::N3,suit: Suit::Clubs},
+        Card{rank: Rank::N4,suit: Suit::Clubs},
+        Card{rank: Rank::N5,suit: Suit::Hearts},
+        Card{rank: Rank::K,suit: Suit::Clubs},
+        Card{rank: Rank::N9,suit: Suit::Diamonds},
+    ], Combination::Flush([
+        Rank::A,
+        Rank::K,
+        Rank::N4,
+        Rank::N3,
+        Rank::N2,
+    ]))]
+    #[case(vec![
+        Card{rank: Rank::N3,suit: Suit::Hearts},
+        Card{rank: Rank::N3,suit: Suit::Spades},
+        Card{rank: Rank::N3,suit: Suit::Clubs},
+        Card{rank: Rank::N2,suit: Suit::Diamonds},
+        Card{rank: Rank::N2,suit: Suit::Spades},
+        Card{rank: Rank::N2,suit: Suit::Hearts},
+        Card{rank: Rank::A,suit: Suit::Diamonds},
+    ], Combination::FullHouse([
+        Rank::N3,
+        Rank::N2,
+    ]))]
+    #[case(vec![
+        Card{rank: Rank::K,suit: Suit::Hearts},
+        Card{rank: Rank::K,suit: Suit::Spades},
+        Card{rank: Rank::Q,suit: Suit::Clubs},
+        Card{rank: Rank::Q,suit: Suit::Diamonds},
+        Card{rank: Rank::N2,suit: Suit::Spades},
+        Card{rank: Rank::N2,suit: Suit::Hearts},
+        Card{rank: Rank::N9,suit: Suit::Diamonds},
+    ], Combination::TwoPairs([
+        Rank::K,
+        Rank::Q,
+        Rank::N9,
+    ]))]
+    #[case(vec![
+        Card{rank: Rank::N2,suit: Suit::Hearts},
+        Card{rank: Rank::N2,suit: Suit::Spades},
+        Card{rank: Rank::N2,suit: Suit::Clubs},
+        Card{rank: Rank::N2,suit: Suit::Diamonds},
+        Card{rank: Rank::N3,suit: Suit::Spades},
+        Card{rank: Rank::N3,suit: Suit::Hearts},
+        Card{rank: Rank::K,suit: Suit::Diamonds},
+    ], Combination::FourOfAKind([
+        Rank::N2,
+        Rank::K,
+    ]))]
     fn best_combination_matches(#[case] cards: Vec<Card>, #[case] expected: Combination) {
-        let mut cache = Default::default();
-        let result = best_combination(&cards, &mut cache);
+        let result = best_combination(cards.iter().collect(), Variant::Holdem);
         assert_eq!(result, expected.score());
     }
 
+    #[rstest]
+    fn best_combination_matches_best_of_five_card_subsets() {
+        let deck = full_deck();
+        // simple deterministic pseudo-random sampling of 7-card sets
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        for _ in 0..2000 {
+            let mut cards: Vec<Card> = Vec::with_capacity(7);
+            while cards.len() < 7 {
+                state = state
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                let card = deck[(state >> 33) as usize % deck.len()];
+                if !cards.contains(&card) {
+                    cards.push(card);
+                }
+            }
+            let best_of_subsets = cards
+                .iter()
+                .cloned()
+                .combinations(COMBINATION_SIZE)
+                .map(|subset| best_combination(subset.into_iter().collect(), Variant::Holdem))
+                .max()
+                .unwrap();
+            assert_eq!(
+                best_combination(cards.iter().collect(), Variant::Holdem),
+                best_of_subsets,
+                "{cards:?}"
+            );
+        }
+    }
+
     #[rstest]
     #[case(Combination::HighCard([
         Rank::J,
@@ -622,10 +1652,207 @@ mod tests {
         #[case] rhs: Combination,
         #[case] expected: Ordering,
     ) {
-        let result = lhs.cmp(&rhs);
+        let result = lhs.score().cmp(&rhs.score());
         assert_eq!(result, expected)
     }
 
+    #[rstest]
+    // wheel loses to six-high straight
+    #[case(vec![
+        Card{rank: Rank::A,suit: Suit::Hearts},
+        Card{rank: Rank::N2,suit: Suit::Spades},
+        Card{rank: Rank::N3,suit: Suit::Clubs},
+        Card{rank: Rank::N4,suit: Suit::Diamonds},
+        Card{rank: Rank::N5,suit: Suit::Hearts},
+        Card{rank: Rank::K,suit: Suit::Diamonds},
+        Card{rank: Rank::N9,suit: Suit::Clubs},
+    ], vec![
+        Card{rank: Rank::N6,suit: Suit::Hearts},
+        Card{rank: Rank::N2,suit: Suit::Spades},
+        Card{rank: Rank::N3,suit: Suit::Clubs},
+        Card{rank: Rank::N4,suit: Suit::Diamonds},
+        Card{rank: Rank::N5,suit: Suit::Hearts},
+        Card{rank: Rank::K,suit: Suit::Diamonds},
+        Card{rank: Rank::N9,suit: Suit::Clubs},
+    ], Ordering::Less)]
+    // wheel beats three of a kind
+    #[case(vec![
+        Card{rank: Rank::A,suit: Suit::Hearts},
+        Card{rank: Rank::N2,suit: Suit::Spades},
+        Card{rank: Rank::N3,suit: Suit::Clubs},
+        Card{rank: Rank::N4,suit: Suit::Diamonds},
+        Card{rank: Rank::N5,suit: Suit::Hearts},
+        Card{rank: Rank::K,suit: Suit::Diamonds},
+        Card{rank: Rank::N9,suit: Suit::Clubs},
+    ], vec![
+        Card{rank: Rank::K,suit: Suit::Hearts},
+        Card{rank: Rank::K,suit: Suit::Spades},
+        Card{rank: Rank::N3,suit: Suit::Clubs},
+        Card{rank: Rank::N4,suit: Suit::Diamonds},
+        Card{rank: Rank::N5,suit: Suit::Hearts},
+        Card{rank: Rank::K,suit: Suit::Diamonds},
+        Card{rank: Rank::N9,suit: Suit::Clubs},
+    ], Ordering::Greater)]
+    // two wheels tie regardless of suits
+    #[case(vec![
+        Card{rank: Rank::A,suit: Suit::Hearts},
+        Card{rank: Rank::N2,suit: Suit::Spades},
+        Card{rank: Rank::N3,suit: Suit::Clubs},
+        Card{rank: Rank::N4,suit: Suit::Diamonds},
+        Card{rank: Rank::N5,suit: Suit::Hearts},
+        Card{rank: Rank::K,suit: Suit::Diamonds},
+        Card{rank: Rank::N9,suit: Suit::Clubs},
+    ], vec![
+        Card{rank: Rank::A,suit: Suit::Spades},
+        Card{rank: Rank::N2,suit: Suit::Hearts},
+        Card{rank: Rank::N3,suit: Suit::Clubs},
+        Card{rank: Rank::N4,suit: Suit::Diamonds},
+        Card{rank: Rank::N5,suit: Suit::Hearts},
+        Card{rank: Rank::K,suit: Suit::Diamonds},
+        Card{rank: Rank::N9,suit: Suit::Clubs},
+    ], Ordering::Equal)]
+    // steel wheel loses to six-high straight flush
+    #[case(vec![
+        Card{rank: Rank::A,suit: Suit::Clubs},
+        Card{rank: Rank::N2,suit: Suit::Clubs},
+        Card{rank: Rank::N3,suit: Suit::Clubs},
+        Card{rank: Rank::N4,suit: Suit::Clubs},
+        Card{rank: Rank::N5,suit: Suit::Clubs},
+        Card{rank: Rank::K,suit: Suit::Diamonds},
+        Card{rank: Rank::N9,suit: Suit::Hearts},
+    ], vec![
+        Card{rank: Rank::N6,suit: Suit::Clubs},
+        Card{rank: Rank::N2,suit: Suit::Clubs},
+        Card{rank: Rank::N3,suit: Suit::Clubs},
+        Card{rank: Rank::N4,suit: Suit::Clubs},
+        Card{rank: Rank::N5,suit: Suit::Clubs},
+        Card{rank: Rank::K,suit: Suit::Diamonds},
+        Card{rank: Rank::N9,suit: Suit::Hearts},
+    ], Ordering::Less)]
+    // steel wheel beats four of a kind
+    #[case(vec![
+        Card{rank: Rank::A,suit: Suit::Clubs},
+        Card{rank: Rank::N2,suit: Suit::Clubs},
+        Card{rank: Rank::N3,suit: Suit::Clubs},
+        Card{rank: Rank::N4,suit: Suit::Clubs},
+        Card{rank: Rank::N5,suit: Suit::Clubs},
+        Card{rank: Rank::K,suit: Suit::Diamonds},
+        Card{rank: Rank::N9,suit: Suit::Hearts},
+    ], vec![
+        Card{rank: Rank::K,suit: Suit::Clubs},
+        Card{rank: Rank::K,suit: Suit::Hearts},
+        Card{rank: Rank::K,suit: Suit::Spades},
+        Card{rank: Rank::N4,suit: Suit::Clubs},
+        Card{rank: Rank::N5,suit: Suit::Clubs},
+        Card{rank: Rank::K,suit: Suit::Diamonds},
+        Card{rank: Rank::N9,suit: Suit::Hearts},
+    ], Ordering::Greater)]
+    fn wheel_cmp_matches(
+        #[case] lhs: Vec<Card>,
+        #[case] rhs: Vec<Card>,
+        #[case] expected: Ordering,
+    ) {
+        let lhs = best_combination(lhs.iter().collect(), Variant::Holdem);
+        let rhs = best_combination(rhs.iter().collect(), Variant::Holdem);
+        assert_eq!(lhs.cmp(&rhs), expected);
+    }
+
+    const SHORT_DECK: Variant = Variant::ShortDeck {
+        trips_beat_straight: false,
+    };
+    const SHORT_DECK_TRIPS: Variant = Variant::ShortDeck {
+        trips_beat_straight: true,
+    };
+
+    fn cards(notation: &str) -> CardSet {
+        notation
+            .split_whitespace()
+            .map(|card| {
+                let chars = card.chars().collect_vec();
+                Card {
+                    rank: Rank::ALL["23456789TJQKA".find(chars[0]).unwrap()],
+                    suit: Suit::ALL["hdsc".find(chars[1]).unwrap()],
+                }
+            })
+            .collect()
+    }
+
+    #[rstest]
+    #[case(Variant::Holdem, "Ah 6d 7s 8c 9h Kd Kc", Combination::Pair([Rank::K, Rank::A, Rank::N9, Rank::N8]))]
+    #[case(SHORT_DECK, "Ah 6d 7s 8c 9h Kd Kc", Combination::Straight(Rank::N9))]
+    #[case(SHORT_DECK, "Ah 6d 7s 8c 9h Td Kc", Combination::Straight(Rank::N10))]
+    #[case(SHORT_DECK, "6h 7d 8s 9c Th Td Ts", Combination::Straight(Rank::N10))]
+    #[case(SHORT_DECK_TRIPS, "6h 7d 8s 9c Th Td Ts", Combination::ThreeOfAKind([Rank::N10, Rank::N9, Rank::N8]))]
+    #[case(
+        SHORT_DECK,
+        "Ah 6h 7h 8h 9h Kd Kc",
+        Combination::StraightFlush(Rank::N9)
+    )]
+    fn short_deck_best_combination_matches(
+        #[case] variant: Variant,
+        #[case] notation: &str,
+        #[case] expected: Combination,
+    ) {
+        assert_eq!(
+            best_combination_from_set(cards(notation), variant),
+            expected
+        );
+    }
+
+    #[rstest]
+    // flush and full house
+    #[case(Variant::Holdem, "Ah Kh 9h 7h 6h", "6d 6s 6c 7d 7s", Ordering::Less)]
+    #[case(SHORT_DECK, "Ah Kh 9h 7h 6h", "6d 6s 6c 7d 7s", Ordering::Greater)]
+    // three of a kind and the lowest straight
+    #[case(SHORT_DECK, "Ad As Ac 7h 6h", "Ah 6d 7s 8c 9h", Ordering::Less)]
+    #[case(
+        SHORT_DECK_TRIPS,
+        "Ad As Ac 7h 6h",
+        "Ah 6d 7s 8c 9h",
+        Ordering::Greater
+    )]
+    // four of a kind still beats a flush
+    #[case(SHORT_DECK, "6d 6s 6c 6h 7s", "Ah Kh 9h 7h 6h", Ordering::Greater)]
+    fn short_deck_cmp_matches(
+        #[case] variant: Variant,
+        #[case] lhs: &str,
+        #[case] rhs: &str,
+        #[case] expected: Ordering,
+    ) {
+        let lhs = best_combination(cards(lhs), variant);
+        let rhs = best_combination(cards(rhs), variant);
+        assert_eq!(lhs.cmp(&rhs), expected);
+    }
+
+    #[rstest]
+    fn solve_short_deck() {
+        let deck = deck(SHORT_DECK);
+        assert_eq!(deck.len(), 36);
+        let table = Table {
+            hand: cards("Ah Kh").iter().collect(),
+            board: cards("Qh Jh 6s")
+                .iter()
+                .map(Some)
+                .chain([None, None])
+                .collect(),
+            variant: SHORT_DECK,
+            ..Default::default()
+        };
+
+        let result = block_on(solve_with_deck(&table, &deck)).unwrap();
+
+        // 31 remaining cards, any 2 of them are dealt to the opponent or to the board
+        assert_eq!(result.hands.len(), 31 * 30 / 2);
+        assert_eq!(result.board_possibilities, 29 * 28 / 2);
+        let combination_counts = result.combination_counts.unwrap();
+        assert_eq!(combination_counts.iter().sum::<u64>(), 31 * 30 / 2);
+        // royal flush with the ten of hearts, short deck wheel in hearts would need 4 more cards
+        assert_eq!(
+            combination_counts[CombinationKind::StraightFlush as usize],
+            30
+        );
+    }
+
     #[rstest]
     fn solve_with_reduced_deck() {
         let deck = vec![
@@ -677,9 +1904,9 @@ mod tests {
         let table = Table {
             hand: vec![deck[0], deck[1]].into_boxed_slice(),
             board: vec![Some(deck[3]), Some(deck[4]), Some(deck[5]), None, None].into_boxed_slice(),
+            ..Default::default()
         };
-        let mut cache = Default::default();
-        let result = block_on(solve_with_deck(&table, &deck, &mut cache)).unwrap();
+        let result = block_on(solve_with_deck(&table, &deck)).unwrap();
         // From 11 deck cards, 2 are mine, 2 are opponents, 3 are on board -> 4 remains to be chosen from into 2 empty board slots.
         // 4 choose 2 = 4! / (2! + (4-2)! = 24 / 4 = 6
         assert_eq!(result.board_possibilities, 6);
@@ -687,6 +1914,265 @@ mod tests {
         assert_eq!(result.hands.len(), 15);
     }
 
+    #[rstest]
+    fn solve_with_dead_cards_matches_reduced_deck() {
+        let deck = full_deck();
+        let dead = vec![deck[2], deck[30], deck[47]];
+        let table = Table {
+            hand: vec![deck[12], deck[11]].into_boxed_slice(),
+            board: vec![Some(deck[10]), Some(deck[22]), Some(deck[3]), None, None]
+                .into_boxed_slice(),
+            dead: dead.clone().into_boxed_slice(),
+            ..Default::default()
+        };
+        let result = block_on(solve_with_deck(&table, &deck)).unwrap();
+
+        let reduced_deck = deck
+            .iter()
+            .cloned()
+            .filter(|c| !dead.contains(c))
+            .collect_vec();
+        let reduced_table = Table {
+            dead: Default::default(),
+            ..table
+        };
+        let expected = block_on(solve_with_deck(&reduced_table, &reduced_deck)).unwrap();
+
+        assert_eq!(
+            serde_json::to_string(&result).unwrap(),
+            serde_json::to_string(&expected).unwrap()
+        );
+    }
+
+    #[rstest]
+    #[case(vec![deck_card(12)])]
+    #[case(vec![deck_card(3)])]
+    #[case(vec![deck_card(40), deck_card(40)])]
+    fn solve_rejects_duplicate_dead_cards(#[case] dead: Vec<Card>) {
+        let deck = full_deck();
+        let table = Table {
+            hand: vec![deck[12], deck[11]].into_boxed_slice(),
+            board: vec![Some(deck[10]), Some(deck[22]), Some(deck[3]), None, None]
+                .into_boxed_slice(),
+            dead: dead.into_boxed_slice(),
+            ..Default::default()
+        };
+        assert!(block_on(solve_with_deck(&table, &deck)).is_err());
+    }
+
+    fn deck_card(i: usize) -> Card {
+        full_deck()[i]
+    }
+
+    #[rstest]
+    fn solve_multiway_matches_brute_force() {
+        let deck = full_deck()
+            .iter()
+            .cloned()
+            .filter(|c| c.rank >= Rank::N9)
+            .collect_vec();
+        let table = Table {
+            hand: vec![deck[0], deck[7]].into_boxed_slice(),
+            board: vec![Some(deck[1]), Some(deck[13]), Some(deck[20]), None, None]
+                .into_boxed_slice(),
+            players: 3,
+            ..Default::default()
+        };
+        let result = block_on(solve_with_deck(&table, &deck)).unwrap();
+        let multiway = result.multiway.unwrap();
+
+        let fixed: CardSet = table
+            .hand
+            .iter()
+            .chain(table.board.iter().flatten())
+            .collect();
+        let remaining = deck
+            .iter()
+            .cloned()
+            .filter(|&c| !fixed.contains(c))
+            .collect_vec();
+        let my_cards: CardSet = table.hand.iter().collect();
+        let board: CardSet = table.board.iter().flatten().collect();
+        let (mut possibilities, mut win_count, mut tie_counts) = (0, 0, [0, 0]);
+        for runout in remaining.iter().combinations(2) {
+            let board = board | runout.into_iter().collect();
+            let rest = remaining
+                .iter()
+                .cloned()
+                .filter(|&c| !board.contains(c))
+                .collect_vec();
+            let hands = rest
+                .iter()
+                .combinations(2)
+                .map(|h| h.into_iter().collect::<CardSet>());
+            for (hand1, hand2) in hands.tuple_combinations() {
+                if !hand1.is_disjoint(hand2) {
+                    continue;
+                }
+                possibilities += 1;
+                let me = best_combination(board | my_cards, Variant::Holdem);
+                let opponents =
+                    [hand1, hand2].map(|h| best_combination(board | h, Variant::Holdem));
+                if opponents.iter().all(|&o| o < me) {
+                    win_count += 1;
+                } else if opponents.iter().all(|&o| o <= me) {
+                    tie_counts[opponents.iter().filter(|&&o| o == me).count() - 1] += 1;
+                }
+            }
+        }
+
+        assert_eq!(multiway.opponents, 2);
+        assert_eq!(multiway.possibilities, possibilities);
+        assert_eq!(multiway.win_count, win_count);
+        assert_eq!(multiway.tie_counts.as_ref(), tie_counts.as_slice());
+        assert_eq!(
+            multiway.lose_count,
+            possibilities - win_count - tie_counts.iter().sum::<u64>()
+        );
+        // per hand results are still against each single opponent hand
+        assert_eq!(result.hands.len(), 171);
+    }
+
+    #[rstest]
+    fn solve_multiway_royal_straight() {
+        let deck = full_deck();
+        let len = deck.len();
+        let table = Table {
+            hand: vec![deck[len - 1], deck[len - 2]].into_boxed_slice(),
+            board: vec![
+                Some(deck[len - 3]),
+                Some(deck[len - 4]),
+                Some(deck[len - 5]),
+                Some(deck[0]),
+                Some(deck[1]),
+            ]
+            .into_boxed_slice(),
+            players: 4,
+            ..Default::default()
+        };
+
+        let result = block_on(solve_with_deck(&table, &deck)).unwrap();
+        let multiway = result.multiway.unwrap();
+
+        assert_eq!(multiway.win_count, multiway.possibilities);
+        assert_eq!(multiway.lose_count, 0);
+        assert_eq!(multiway.equity(), 1.0);
+    }
+
+    #[rstest]
+    fn solve_with_opponent_range_matches_full_solve() {
+        let deck = full_deck();
+        let mut table = Table {
+            hand: vec![deck[12], deck[25]].into_boxed_slice(),
+            board: vec![
+                Some(deck[11]),
+                Some(deck[24]),
+                Some(deck[3]),
+                Some(deck[16]),
+                None,
+            ]
+            .into_boxed_slice(),
+            ..Default::default()
+        };
+        let full = block_on(solve_with_deck(&table, &deck)).unwrap();
+
+        table.opponent_range = Some("QQ+, AKs, 54s:0.5, 2h3d".parse().unwrap());
+        let result = block_on(solve_with_deck(&table, &deck)).unwrap();
+
+        // AA and KK have 2 of their cards used, and so do 54s and AKs 2 of their suits
+        assert_eq!(result.hands.len(), 1 + 1 + 6 + 2 + 2 + 1);
+        for hand in result.hands.iter() {
+            let full_hand = full.hands.iter().find(|h| h.hand == hand.hand).unwrap();
+            assert_eq!(hand.beats_me_count, full_hand.beats_me_count);
+            assert_eq!(hand.is_beaten_count, full_hand.is_beaten_count);
+            let expected_weight = if hand.hand[0].rank == Rank::N5 {
+                0.5
+            } else {
+                1.0
+            };
+            assert_eq!(hand.weight, expected_weight);
+        }
+        assert_eq!(result.board_possibilities, full.board_possibilities);
+    }
+
+    #[rstest]
+    fn solve_with_impossible_opponent_range() {
+        let deck = full_deck();
+        let table = Table {
+            hand: vec![deck[12], deck[25]].into_boxed_slice(),
+            board: vec![Some(deck[38]), Some(deck[1]), Some(deck[2]), None, None]
+                .into_boxed_slice(),
+            opponent_range: Some("AA".parse().unwrap()),
+            ..Default::default()
+        };
+        assert!(block_on(solve_with_deck(&table, &deck)).is_err());
+    }
+
+    #[rstest]
+    fn solve_range_vs_range_matches_single_hand_solve() {
+        let deck = full_deck();
+        let board = vec![
+            Some(deck[11]),
+            Some(deck[24]),
+            Some(deck[3]),
+            Some(deck[16]),
+            None,
+        ];
+        let opponent_range: Range = "QQ+, AKs, 54s".parse().unwrap();
+        let table = Table {
+            hand: vec![deck[12], deck[25]].into_boxed_slice(),
+            board: board.clone().into_boxed_slice(),
+            opponent_range: Some(opponent_range.clone()),
+            ..Default::default()
+        };
+        let single = block_on(solve_with_deck(&table, &deck)).unwrap();
+
+        let my_range: Range = "AhAd".parse().unwrap();
+        let result = block_on(solve_range_vs_range(
+            &my_range,
+            &opponent_range,
+            &board,
+            &deck,
+            Variant::Holdem,
+        ))
+        .unwrap();
+
+        assert_eq!(result.hands.len(), 1);
+        let hand = &result.hands[0];
+        let win_count: u64 = single.hands.iter().map(|h| h.is_beaten_count).sum();
+        let lose_count: u64 = single.hands.iter().map(|h| h.beats_me_count).sum();
+        let all_count = single.hands.len() as u64 * single.board_possibilities;
+        assert_eq!(hand.win_count, win_count as f64);
+        assert_eq!(hand.lose_count, lose_count as f64);
+        assert_eq!(hand.tie_count, (all_count - win_count - lose_count) as f64);
+        assert_eq!(result.my_equity, hand.equity());
+        assert_eq!(result.my_equity + result.opponent_equity, 1.0);
+    }
+
+    #[rstest]
+    fn solve_same_ranges_have_equal_equity() {
+        let deck = full_deck();
+        let board = vec![
+            Some(deck[0]),
+            Some(deck[14]),
+            Some(deck[28]),
+            Some(deck[42]),
+            None,
+        ];
+        let range: Range = "TT+, AJs+, KQo".parse().unwrap();
+        let result = block_on(solve_range_vs_range(
+            &range,
+            &range,
+            &board,
+            &deck,
+            Variant::Holdem,
+        ))
+        .unwrap();
+
+        assert!((result.my_equity - 0.5).abs() < 1e-12);
+        assert!((result.opponent_equity - 0.5).abs() < 1e-12);
+    }
+
     #[rstest]
     fn solve_flop_royal_straight() {
         let deck = full_deck();
@@ -701,10 +2187,10 @@ mod tests {
                 None,
             ]
             .into_boxed_slice(),
+            ..Default::default()
         };
 
-        let mut cache = Default::default();
-        let result = block_on(solve_with_deck(&table, &deck, &mut cache)).unwrap();
+        let result = block_on(solve_with_deck(&table, &deck)).unwrap();
 
         // From 52 deck cards, 2 are mine, 2 are opponents, 3 are on board -> 45 remains to be chosen from into 2 empty board slots.
         // 45 choose 2 = 45! / (2! + (45-2)! = 24 / 4 = 6
@@ -715,9 +2201,351 @@ mod tests {
         assert_eq!(result.win_count, result.hands.len() as u64);
         assert_eq!(result.lose_count, 0);
 
+        let mut expected_combination_counts = [0; COMBINATION_KIND_COUNT];
+        expected_combination_counts[CombinationKind::StraightFlush as usize] = 1081;
+        assert_eq!(result.combination_counts, Some(expected_combination_counts));
+
         assert_royal_straigth_always_wins(table, &result);
     }
 
+    #[rstest]
+    fn solve_counts_my_final_combinations() {
+        let deck = full_deck();
+        // AhKh on 2h 7h 9c Td
+        let table = Table {
+            hand: vec![deck[12], deck[11]].into_boxed_slice(),
+            board: vec![
+                Some(deck[0]),
+                Some(deck[5]),
+                Some(deck[46]),
+                Some(deck[21]),
+                None,
+            ]
+            .into_boxed_slice(),
+            ..Default::default()
+        };
+        let result = block_on(solve_with_deck(&table, &deck)).unwrap();
+
+        // 9 hearts make a flush, 3 of each A, K, 2, 7 and 2 of each 9, T make a pair
+        let mut expected_combination_counts = [0; COMBINATION_KIND_COUNT];
+        expected_combination_counts[CombinationKind::Flush as usize] = 9;
+        expected_combination_counts[CombinationKind::Pair as usize] = 16;
+        expected_combination_counts[CombinationKind::HighCard as usize] = 46 - 9 - 16;
+        assert_eq!(result.combination_counts, Some(expected_combination_counts));
+        assert_eq!(
+            result.combination_probabilities().unwrap()[CombinationKind::Flush as usize],
+            9.0 / 46.0
+        );
+    }
+
+    #[rstest]
+    fn solve_counts_combinations_beating_me() {
+        let deck = full_deck();
+        // AhKh on 2h 7h 9c Td against 9s9d
+        let table = Table {
+            hand: vec![deck[12], deck[11]].into_boxed_slice(),
+            board: vec![
+                Some(deck[0]),
+                Some(deck[5]),
+                Some(deck[46]),
+                Some(deck[21]),
+                None,
+            ]
+            .into_boxed_slice(),
+            opponent_range: Some("9s9d".parse().unwrap()),
+            ..Default::default()
+        };
+        let result = block_on(solve_with_deck(&table, &deck)).unwrap();
+
+        // 7 hearts not pairing the board win, 9h gives quads, each remaining 2, 7 and T gives
+        // full house
+        let mut expected_counts = [0; COMBINATION_KIND_COUNT];
+        expected_counts[CombinationKind::FourOfAKind as usize] = 1;
+        expected_counts[CombinationKind::FullHouse as usize] = 9;
+        expected_counts[CombinationKind::ThreeOfAKind as usize] = 44 - 7 - 1 - 9;
+        assert_eq!(result.hands[0].beats_me_count, 44 - 7);
+        assert_eq!(result.hands[0].beats_me_combination_counts, expected_counts);
+        assert_eq!(
+            result.beats_me_combination_counts(),
+            expected_counts.map(|count| count as f64)
+        );
+        assert_eq!(result.win_probability(), 7.0 / 44.0);
+        assert_eq!(result.tie_probability(), 0.0);
+        assert_eq!(result.equity(), 7.0 / 44.0);
+    }
+
+    #[rstest]
+    fn hand_counts_add_up() {
+        let deck = full_deck();
+        let table = Table {
+            hand: vec![deck[12], deck[11]].into_boxed_slice(),
+            board: vec![Some(deck[0]), Some(deck[5]), Some(deck[46]), None, None]
+                .into_boxed_slice(),
+            ..Default::default()
+        };
+        let result = block_on(solve_with_deck(&table, &deck)).unwrap();
+
+        for hand in result.hands.iter() {
+            assert_eq!(
+                hand.beats_me_combination_counts.iter().sum::<u64>(),
+                hand.beats_me_count
+            );
+            assert_eq!(
+                hand.beats_me_count + hand.tie_count + hand.is_beaten_count,
+                result.board_possibilities
+            );
+        }
+    }
+
+    #[rstest]
+    fn partial_solution_extrapolates_counted_runouts() {
+        let deck = full_deck();
+        let table = Table {
+            hand: vec![deck[12], deck[11]].into_boxed_slice(),
+            board: vec![Some(deck[0]), Some(deck[5]), Some(deck[46]), None, None]
+                .into_boxed_slice(),
+            ..Default::default()
+        };
+        let table_cards = table_cards(&table, &deck).unwrap();
+        let mut evaluator = RunoutEvaluator::new(&table_cards);
+        let mut counts = RunoutCounts::new(&table_cards);
+        let mut fill_cards_map_i = Vec::from_iter(0..table_cards.choose);
+        let mut partial = None;
+        loop {
+            let fill_cards: CardSet = fill_cards_map_i
+                .iter()
+                .map(|&i| table_cards.remaining_deck[i])
+                .collect();
+            evaluator.evaluate(fill_cards, &mut counts, None);
+            if counts.runouts == 500 {
+                partial = Some(counts.partial_solution(&table_cards));
+            }
+            if !next_combination(&mut fill_cards_map_i, table_cards.remaining_deck.len()) {
+                break;
+            }
+        }
+        let partial = partial.unwrap();
+
+        for hand in partial.hands.iter() {
+            assert_eq!(
+                hand.beats_me_combination_counts.iter().sum::<u64>(),
+                hand.beats_me_count
+            );
+            assert_eq!(
+                hand.beats_me_count + hand.tie_count + hand.is_beaten_count,
+                partial.board_possibilities
+            );
+        }
+        assert_eq!(
+            serde_json::to_string(&counts.partial_solution(&table_cards)).unwrap(),
+            serde_json::to_string(&counts.into_solution(&table_cards)).unwrap()
+        );
+    }
+
+    #[rstest]
+    fn solve_preflop_without_precalculated_solution_is_approximate() {
+        let deck = full_deck();
+        let table = Table {
+            hand: vec![deck[12], deck[11]].into_boxed_slice(),
+            board: vec![None; BOARD_SIZE].into_boxed_slice(),
+            dead: vec![deck[30]].into_boxed_slice(),
+            ..Default::default()
+        };
+
+        let result = block_on(solve(signal::AbortSignal::new(), &table)).unwrap();
+
+        let approximation = result.approximation.unwrap();
+        assert_eq!(
+            result.board_possibilities,
+            SamplingConfig::default().samples_per_hand
+        );
+        let (lower, upper) = approximation.equity_bounds();
+        assert!(lower < approximation.equity && approximation.equity < upper);
+    }
+
+    #[rstest]
+    fn solve_multiway_flop_is_sampled() {
+        let deck = full_deck();
+        let table = Table {
+            hand: vec![deck[12], deck[11]].into_boxed_slice(),
+            board: vec![Some(deck[0]), Some(deck[5]), Some(deck[46]), None, None]
+                .into_boxed_slice(),
+            players: 4,
+            ..Default::default()
+        };
+        let start = Instant::now();
+
+        let result = block_on(solve(signal::AbortSignal::new(), &table)).unwrap();
+
+        // enumerating every set of 3 opponents' hands on each runout takes hours
+        assert!(start.elapsed() < Duration::from_secs(60));
+        let approximation = result.approximation.unwrap();
+        assert!(approximation.multiway_equity_standard_error.unwrap() > 0.0);
+        assert_eq!(result.multiway.unwrap().opponents, 3);
+    }
+
+    #[rstest]
+    #[case(2, Duration::from_secs(60), false)]
+    #[case(2, Duration::ZERO, true)]
+    #[case(3, Duration::from_secs(60), false)]
+    #[case(3, Duration::ZERO, true)]
+    fn solve_with_time_budget_samples_when_exceeded(
+        #[case] players: usize,
+        #[case] time_budget: Duration,
+        #[case] approximate: bool,
+    ) {
+        let deck = full_deck();
+        let table = Table {
+            hand: vec![deck[12], deck[11]].into_boxed_slice(),
+            board: vec![Some(deck[0]), Some(deck[5]), Some(deck[46]), None, None]
+                .into_boxed_slice(),
+            players,
+            ..Default::default()
+        };
+        let exact = block_on(solve_with_deck(&table, &deck)).unwrap();
+        let result = block_on(solve_with_deck_time_budget(
+            &table,
+            &deck,
+            time_budget,
+            &mut ProgressReporter::new(None),
+        ))
+        .unwrap();
+
+        match result.approximation {
+            Some(approximation) => {
+                assert!(approximate);
+                match (&result.multiway, &exact.multiway) {
+                    (Some(multiway), Some(exact_multiway)) => {
+                        let standard_error = approximation.multiway_equity_standard_error.unwrap();
+                        assert!(
+                            (multiway.equity() - exact_multiway.equity()).abs()
+                                < 4.0 * standard_error
+                        );
+                    }
+                    _ => {
+                        let (lower, upper) = approximation.equity_bounds();
+                        assert!(lower <= exact.equity() && exact.equity() <= upper);
+                    }
+                }
+            }
+            None => {
+                assert!(!approximate);
+                assert_eq!(
+                    serde_json::to_string(&result).unwrap(),
+                    serde_json::to_string(&exact).unwrap()
+                );
+            }
+        }
+    }
+
+    #[rstest]
+    fn solve_with_time_budget_stops_within_runout() {
+        let deck = full_deck();
+        let table = Table {
+            hand: vec![deck[12], deck[11]].into_boxed_slice(),
+            board: vec![
+                Some(deck[0]),
+                Some(deck[5]),
+                Some(deck[46]),
+                Some(deck[20]),
+                Some(deck[33]),
+            ]
+            .into_boxed_slice(),
+            players: 6,
+            ..Default::default()
+        };
+        let time_budget = Duration::from_millis(200);
+        let start = Instant::now();
+
+        let result = block_on(solve_with_deck_time_budget(
+            &table,
+            &deck,
+            time_budget,
+            &mut ProgressReporter::new(None),
+        ))
+        .unwrap();
+
+        // the river has a single runout, but its sets of 5 opponents' hands take minutes, the margin
+        // is for the minimum samples of each hand
+        assert!(start.elapsed() < time_budget + Duration::from_millis(500));
+        assert!(result.approximation.is_some());
+        assert_eq!(result.multiway.unwrap().opponents, 5);
+    }
+
+    #[rstest]
+    fn solve_with_time_budget_keeps_exact_hands() {
+        let deck = full_deck();
+        let table = Table {
+            hand: vec![deck[12], deck[11]].into_boxed_slice(),
+            board: vec![Some(deck[0]), Some(deck[5]), Some(deck[46]), None, None]
+                .into_boxed_slice(),
+            ..Default::default()
+        };
+        let exact = block_on(solve_with_deck(&table, &deck)).unwrap();
+        let table_cards = table_cards(&table, &deck).unwrap();
+        let start = Instant::now();
+        block_on(solve_hands_within(
+            &table_cards,
+            Duration::from_secs(60),
+            &mut ProgressReporter::new(None),
+        ))
+        .unwrap();
+        // half of the hands fit, the first one is evaluated before sampling the rest
+        let time_budget = start.elapsed() / 2;
+        let first_hand = table_cards.hands[0].hand.clone();
+        let result = block_on(solve_with_deck_time_budget(
+            &table,
+            &deck,
+            time_budget,
+            &mut ProgressReporter::new(None),
+        ))
+        .unwrap();
+
+        let hand_counts = |solution: &Solution| {
+            let hand = solution
+                .hands
+                .iter()
+                .find(|hand| hand.hand == first_hand)
+                .unwrap();
+            serde_json::to_string(hand).unwrap()
+        };
+        assert_eq!(result.board_possibilities, exact.board_possibilities);
+        assert_eq!(hand_counts(&result), hand_counts(&exact));
+        assert_eq!(result.combination_counts, exact.combination_counts);
+        assert!(result.hands.iter().all(|hand| hand.beats_me_count
+            + hand.is_beaten_count
+            + hand.tie_count
+            == result.board_possibilities));
+        let approximation = result.approximation.unwrap();
+        let (lower, upper) = approximation.equity_bounds();
+        assert!(lower <= exact.equity() && exact.equity() <= upper);
+    }
+
+    #[rstest]
+    fn solve_preflop_with_time_budget_stays_within_it() {
+        let deck = full_deck();
+        let table = Table {
+            hand: vec![deck[12], deck[11]].into_boxed_slice(),
+            board: vec![None; BOARD_SIZE].into_boxed_slice(),
+            dead: vec![deck[30]].into_boxed_slice(),
+            ..Default::default()
+        };
+        let time_budget = Duration::from_millis(200);
+        let start = Instant::now();
+
+        let result = block_on(solve_with_deck_time_budget(
+            &table,
+            &deck,
+            time_budget,
+            &mut ProgressReporter::new(None),
+        ))
+        .unwrap();
+
+        // enumerating over a million runouts alone would exceed the budget, so all hands are sampled
+        assert!(start.elapsed() < time_budget + Duration::from_millis(100));
+        assert!(result.approximation.is_some());
+    }
+
     #[rstest]
     fn solve_turn_royal_straight() {
         let deck = full_deck();
@@ -732,10 +2560,10 @@ mod tests {
                 Some(deck[1]),
             ]
             .into_boxed_slice(),
+            ..Default::default()
         };
 
-        let mut cache = Default::default();
-        let result = block_on(solve_with_deck(&table, &deck, &mut cache)).unwrap();
+        let result = block_on(solve_with_deck(&table, &deck)).unwrap();
 
         assert_eq!(result.win_count, result.hands.len() as u64);
         assert_eq!(result.lose_count, 0);
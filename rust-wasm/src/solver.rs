@@ -0,0 +1,332 @@
+use crate::progress::ProgressReporter;
+use crate::range::Range;
+use crate::solve::{solve_table_cards, table_cards, RunoutCounts, TableCards};
+use crate::types::{Card, CardSet, CombinationKind, HandSolution, Solution, Table, Variant};
+use anyhow::Result;
+use itertools::Itertools;
+
+// runouts of more cards are not recorded, there would be too many of them
+pub const MAX_RECORDED_CHOOSE: usize = 2;
+
+/// Solver keeping the result of every runout of the last solved table, so that solving the same
+/// table with one more board card only selects the runouts containing that card.
+#[derive(Default)]
+pub struct Solver {
+    state: Option<SolverState>,
+}
+
+struct SolverState {
+    key: TableKey,
+    table_cards: TableCards,
+    record: RunoutRecord,
+}
+
+// everything about the table except the board
+#[derive(PartialEq)]
+struct TableKey {
+    variant: Variant,
+    deck: CardSet,
+    hand: CardSet,
+    dead: CardSet,
+    players: usize,
+    opponent_range: Option<Range>,
+}
+
+impl TableKey {
+    fn new(table: &Table, deck: &[Card]) -> Self {
+        TableKey {
+            variant: table.variant,
+            deck: deck.iter().collect(),
+            hand: table.hand.iter().collect(),
+            dead: table.dead.iter().collect(),
+            players: table.players,
+            opponent_range: table.opponent_range.clone(),
+        }
+    }
+}
+
+impl Solver {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Forgets the runouts of the last solved table.
+    pub fn clear(&mut self) {
+        self.state = None;
+    }
+
+    pub async fn solve(
+        &mut self,
+        table: &Table,
+        deck: &[Card],
+        reporter: &mut ProgressReporter<'_>,
+    ) -> Result<Solution> {
+        if let Some(solution) = self.solve_recorded(table, deck) {
+            return Ok(solution);
+        }
+
+        let table_cards = table_cards(table, deck)?;
+        let key = TableKey::new(table, deck);
+        if table_cards.choose > MAX_RECORDED_CHOOSE {
+            return Ok(solve_table_cards(&table_cards, None, reporter).await);
+        }
+        let mut record = RunoutRecord::new(table_cards.hands.len());
+        let solution = solve_table_cards(&table_cards, Some(&mut record), reporter).await;
+        self.state = Some(SolverState {
+            key,
+            table_cards,
+            record,
+        });
+        Ok(solution)
+    }
+
+    /// Solution from the runouts of the last solved table, without evaluating any runout. `None`
+    /// when the runouts can't be reused, and then they are forgotten.
+    pub fn solve_recorded(&mut self, table: &Table, deck: &[Card]) -> Option<Solution> {
+        let state = self.state.take()?;
+        let table_cards = table_cards(table, deck).ok()?;
+        let key = TableKey::new(table, deck);
+        let record = state.next_record(&key, &table_cards)?;
+        let solution = solution_from_record(&table_cards, &record);
+        self.state = Some(SolverState {
+            key,
+            table_cards,
+            record,
+        });
+        Some(solution)
+    }
+}
+
+impl SolverState {
+    /// Record of the runouts of the next table, if it's the same table or it has one more board card.
+    fn next_record(self, key: &TableKey, table_cards: &TableCards) -> Option<RunoutRecord> {
+        let fixed_board_cards = self.table_cards.fixed_board_cards;
+        if *key != self.key || !fixed_board_cards.is_subset(table_cards.fixed_board_cards) {
+            return None;
+        }
+        let new_cards = table_cards.fixed_board_cards - fixed_board_cards;
+        if new_cards.is_empty() {
+            return Some(self.record);
+        }
+        let [card] = new_cards.iter().collect_vec()[..] else {
+            return None;
+        };
+        let columns = self
+            .table_cards
+            .hands
+            .iter()
+            .positions(|hand| !hand.hand.contains(&card))
+            .collect_vec();
+        // candidate hands keep their order when a card is removed from the deck
+        if columns.len() != table_cards.hands.len() {
+            return None;
+        }
+        Some(self.record.with_card(card, &columns))
+    }
+}
+
+pub(crate) fn solution_from_record(table_cards: &TableCards, record: &RunoutRecord) -> Solution {
+    let mut counts = RunoutCounts::new(table_cards);
+    counts.runouts = record.runouts_count() as u64;
+    let opponents = table_cards.opponents;
+    for runout in 0..record.runouts_count() {
+        counts.combination_counts[record.my_kind(runout) as usize] += 1;
+        for (hand, &outcome) in counts.hands.iter_mut().zip(record.outcomes(runout)) {
+            outcome.count(hand);
+        }
+        if opponents > 1 {
+            for (count, runout_count) in counts
+                .multiway_counts
+                .iter_mut()
+                .zip(record.multiway_counts(runout, opponents))
+            {
+                *count += runout_count;
+            }
+        }
+    }
+    counts.into_solution(table_cards)
+}
+
+/// My result against a candidate hand on a runout.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) enum Outcome {
+    // candidate hand uses a card of the runout
+    Impossible,
+    Win,
+    Tie,
+    // with the combination kind of the candidate hand
+    Lose(CombinationKind),
+}
+
+impl Outcome {
+    pub(crate) fn count(self, hand: &mut HandSolution) {
+        match self {
+            Outcome::Impossible => {}
+            Outcome::Win => hand.is_beaten_count += 1,
+            Outcome::Tie => hand.tie_count += 1,
+            Outcome::Lose(kind) => {
+                hand.beats_me_count += 1;
+                hand.beats_me_combination_counts[kind as usize] += 1;
+            }
+        }
+    }
+}
+
+/// Results of every evaluated runout, in the order of evaluation.
+#[derive(Debug, Clone)]
+pub(crate) struct RunoutRecord {
+    hands_count: usize,
+    fill_cards: Vec<CardSet>,
+    my_kinds: Vec<CombinationKind>,
+    // hands_count outcomes per runout
+    outcomes: Vec<Outcome>,
+    // counts of wins and ties with each count of opponents per runout, only with multiple opponents
+    multiway_counts: Vec<u64>,
+}
+
+impl RunoutRecord {
+    pub(crate) fn new(hands_count: usize) -> Self {
+        RunoutRecord {
+            hands_count,
+            fill_cards: vec![],
+            my_kinds: vec![],
+            outcomes: vec![],
+            multiway_counts: vec![],
+        }
+    }
+
+    pub(crate) fn push_runout(&mut self, fill_cards: CardSet, my_kind: CombinationKind) {
+        self.fill_cards.push(fill_cards);
+        self.my_kinds.push(my_kind);
+    }
+
+    pub(crate) fn push_outcome(&mut self, outcome: Outcome) {
+        self.outcomes.push(outcome);
+    }
+
+    pub(crate) fn push_multiway_counts(&mut self, counts: &[u64]) {
+        self.multiway_counts.extend_from_slice(counts);
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.fill_cards.clear();
+        self.my_kinds.clear();
+        self.outcomes.clear();
+        self.multiway_counts.clear();
+    }
+
+    pub(crate) fn runouts_count(&self) -> usize {
+        self.fill_cards.len()
+    }
+
+    pub(crate) fn my_kind(&self, runout: usize) -> CombinationKind {
+        self.my_kinds[runout]
+    }
+
+    pub(crate) fn outcomes(&self, runout: usize) -> &[Outcome] {
+        &self.outcomes[runout * self.hands_count..(runout + 1) * self.hands_count]
+    }
+
+    pub(crate) fn multiway_counts(&self, runout: usize, opponents: usize) -> &[u64] {
+        &self.multiway_counts[runout * (opponents + 1)..(runout + 1) * (opponents + 1)]
+    }
+
+    /// Runouts containing the card, without it, and only with outcomes of the hands in `columns`.
+    pub(crate) fn with_card(&self, card: Card, columns: &[usize]) -> RunoutRecord {
+        let mut record = RunoutRecord::new(columns.len());
+        let multiway_size = self.multiway_counts.len() / self.runouts_count().max(1);
+        for (runout, &fill_cards) in self.fill_cards.iter().enumerate() {
+            if !fill_cards.contains(card) {
+                continue;
+            }
+            record.push_runout(fill_cards - card.into(), self.my_kinds[runout]);
+            let outcomes = self.outcomes(runout);
+            record
+                .outcomes
+                .extend(columns.iter().map(|&column| outcomes[column]));
+            record.push_multiway_counts(
+                &self.multiway_counts[runout * multiway_size..(runout + 1) * multiway_size],
+            );
+        }
+        record
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solve::{full_deck, solve_with_deck};
+    use futures::executor::block_on;
+    use rstest::rstest;
+
+    fn table_with_board(players: usize, board: [Option<usize>; 5]) -> Table {
+        let deck = full_deck();
+        Table {
+            hand: vec![deck[12], deck[11]].into_boxed_slice(),
+            board: board.map(|i| i.map(|i| deck[i])).into(),
+            players,
+            opponent_range: (players == 2).then(|| "22+, A2s+, K9o+, 76s".parse().unwrap()),
+            ..Default::default()
+        }
+    }
+
+    fn to_json(solution: &Solution) -> String {
+        serde_json::to_string(solution).unwrap()
+    }
+
+    #[rstest]
+    #[case(2)]
+    #[case(3)]
+    fn adding_cards_matches_full_solve(#[case] players: usize) {
+        let deck = full_deck();
+        let mut solver = Solver::new();
+        for board in [
+            [Some(10), Some(22), Some(3), None, None],
+            [Some(10), Some(22), Some(3), None, None],
+            [Some(10), Some(22), Some(3), Some(40), None],
+            [Some(10), Some(22), Some(3), Some(40), Some(7)],
+        ] {
+            let table = table_with_board(players, board);
+            let solution =
+                block_on(solver.solve(&table, &deck, &mut ProgressReporter::new(None))).unwrap();
+            let expected = block_on(solve_with_deck(&table, &deck)).unwrap();
+            assert_eq!(to_json(&solution), to_json(&expected));
+        }
+        let state = solver.state.unwrap();
+        assert_eq!(state.table_cards.choose, 0);
+        assert_eq!(state.record.runouts_count(), 1);
+    }
+
+    #[rstest]
+    fn recorded_runouts_solve_next_street_only() {
+        let deck = full_deck();
+        let mut solver = Solver::new();
+        let flop = table_with_board(2, [Some(10), Some(22), Some(3), None, None]);
+        assert!(solver.solve_recorded(&flop, &deck).is_none());
+        block_on(solver.solve(&flop, &deck, &mut ProgressReporter::new(None))).unwrap();
+
+        let turn = table_with_board(2, [Some(10), Some(22), Some(3), Some(40), None]);
+        let solution = solver.solve_recorded(&turn, &deck).unwrap();
+        let expected = block_on(solve_with_deck(&turn, &deck)).unwrap();
+        assert_eq!(to_json(&solution), to_json(&expected));
+
+        let other_turn = table_with_board(2, [Some(10), Some(22), Some(4), Some(40), None]);
+        assert!(solver.solve_recorded(&other_turn, &deck).is_none());
+        assert!(solver.state.is_none());
+    }
+
+    #[rstest]
+    fn other_table_is_solved_from_scratch() {
+        let deck = full_deck();
+        let mut solver = Solver::new();
+        let flop = table_with_board(2, [Some(10), Some(22), Some(3), None, None]);
+        block_on(solver.solve(&flop, &deck, &mut ProgressReporter::new(None))).unwrap();
+        let other_turn = table_with_board(2, [Some(10), Some(22), Some(4), Some(40), None]);
+        let solution =
+            block_on(solver.solve(&other_turn, &deck, &mut ProgressReporter::new(None))).unwrap();
+        let expected = block_on(solve_with_deck(&other_turn, &deck)).unwrap();
+
+        assert_eq!(to_json(&solution), to_json(&expected));
+        assert_eq!(solver.state.unwrap().record.runouts_count(), 52 - 6);
+    }
+}
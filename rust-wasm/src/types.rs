@@ -1,4 +1,6 @@
+use crate::range::Range;
 use serde_derive::{Deserialize, Serialize};
+use std::ops::{BitAnd, BitOr, Not, Sub};
 use strum_macros::EnumIter;
 
 pub const RANK_COUNT: usize = 13;
@@ -51,6 +53,24 @@ pub enum Rank {
     A,
 }
 
+impl Rank {
+    pub const ALL: [Rank; RANK_COUNT] = [
+        Rank::N2,
+        Rank::N3,
+        Rank::N4,
+        Rank::N5,
+        Rank::N6,
+        Rank::N7,
+        Rank::N8,
+        Rank::N9,
+        Rank::N10,
+        Rank::J,
+        Rank::Q,
+        Rank::K,
+        Rank::A,
+    ];
+}
+
 #[derive(
     EnumIter,
     Default,
@@ -77,6 +97,10 @@ pub enum Suit {
     Clubs,
 }
 
+impl Suit {
+    pub const ALL: [Suit; SUIT_COUNT] = [Suit::Hearts, Suit::Diamonds, Suit::Spades, Suit::Clubs];
+}
+
 #[derive(
     Default, Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Serialize, Deserialize,
 )]
@@ -87,10 +111,262 @@ pub struct Card {
     pub suit: Suit,
 }
 
-#[derive(Debug)]
+// Bits per suit in CardSet, each suit occupies its own 16-bit lane so that the rank mask of
+// a suit can be read directly.
+const SUIT_LANE_BITS: usize = 16;
+const SUIT_LANE_MASK: u64 = (1 << RANK_COUNT) - 1;
+
+/// Set of cards backed by a bitmask - bit `suit * 16 + rank` is set for each contained card.
+#[derive(Default, Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub struct CardSet(u64);
+
+impl CardSet {
+    pub const EMPTY: CardSet = CardSet(0);
+
+    fn bit(card: Card) -> u64 {
+        1 << (card.suit as usize * SUIT_LANE_BITS + card.rank as usize)
+    }
+
+    /// Adds the card, returns whether it was not yet present.
+    pub fn insert(&mut self, card: Card) -> bool {
+        let bit = Self::bit(card);
+        let inserted = self.0 & bit == 0;
+        self.0 |= bit;
+        inserted
+    }
+
+    /// Removes the card, returns whether it was present.
+    pub fn remove(&mut self, card: Card) -> bool {
+        let bit = Self::bit(card);
+        let removed = self.0 & bit != 0;
+        self.0 &= !bit;
+        removed
+    }
+
+    pub fn contains(&self, card: Card) -> bool {
+        self.0 & Self::bit(card) != 0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn is_subset(&self, other: CardSet) -> bool {
+        self.0 & !other.0 == 0
+    }
+
+    pub fn is_disjoint(&self, other: CardSet) -> bool {
+        self.0 & other.0 == 0
+    }
+
+    /// Mask of ranks of cards with given suit, bit `rank` is set for each contained card.
+    pub fn suit_ranks(&self, suit: Suit) -> u16 {
+        ((self.0 >> (suit as usize * SUIT_LANE_BITS)) & SUIT_LANE_MASK) as u16
+    }
+
+    /// Same cards with each suit `s` replaced by `suits[s]`.
+    pub fn permute_suits(&self, suits: &[Suit; SUIT_COUNT]) -> CardSet {
+        let mut permuted = 0;
+        for (&suit, &to) in Suit::ALL.iter().zip(suits) {
+            permuted |= (self.suit_ranks(suit) as u64) << (to as usize * SUIT_LANE_BITS);
+        }
+        CardSet(permuted)
+    }
+
+    /// Iterates cards ordered by suit, then by rank (same order as `full_deck`).
+    pub fn iter(&self) -> CardSetIter {
+        CardSetIter(self.0)
+    }
+}
+
+impl From<Card> for CardSet {
+    fn from(card: Card) -> Self {
+        CardSet(Self::bit(card))
+    }
+}
+
+impl From<Rank> for CardSet {
+    /// All cards of the rank.
+    fn from(rank: Rank) -> Self {
+        Suit::ALL.iter().map(|&suit| Card { rank, suit }).collect()
+    }
+}
+
+impl From<Suit> for CardSet {
+    /// All cards of the suit.
+    fn from(suit: Suit) -> Self {
+        CardSet(SUIT_LANE_MASK << (suit as usize * SUIT_LANE_BITS))
+    }
+}
+
+impl FromIterator<Card> for CardSet {
+    fn from_iter<I: IntoIterator<Item = Card>>(iter: I) -> Self {
+        let mut set = CardSet::EMPTY;
+        set.extend(iter);
+        set
+    }
+}
+
+impl<'a> FromIterator<&'a Card> for CardSet {
+    fn from_iter<I: IntoIterator<Item = &'a Card>>(iter: I) -> Self {
+        iter.into_iter().copied().collect()
+    }
+}
+
+impl Extend<Card> for CardSet {
+    fn extend<I: IntoIterator<Item = Card>>(&mut self, iter: I) {
+        for card in iter {
+            self.insert(card);
+        }
+    }
+}
+
+impl IntoIterator for CardSet {
+    type Item = Card;
+    type IntoIter = CardSetIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl BitOr for CardSet {
+    type Output = CardSet;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        CardSet(self.0 | rhs.0)
+    }
+}
+
+impl BitAnd for CardSet {
+    type Output = CardSet;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        CardSet(self.0 & rhs.0)
+    }
+}
+
+impl Sub for CardSet {
+    type Output = CardSet;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        CardSet(self.0 & !rhs.0)
+    }
+}
+
+impl Not for CardSet {
+    type Output = CardSet;
+
+    /// All cards of a full deck not in this set.
+    fn not(self) -> Self::Output {
+        Suit::ALL
+            .iter()
+            .fold(CardSet::EMPTY, |acc, &suit| acc | CardSet::from(suit))
+            - self
+    }
+}
+
+pub struct CardSetIter(u64);
+
+impl Iterator for CardSetIter {
+    type Item = Card;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.0 == 0 {
+            return None;
+        }
+        let bit_i = self.0.trailing_zeros() as usize;
+        self.0 &= self.0 - 1;
+        Some(Card {
+            rank: Rank::ALL[bit_i % SUIT_LANE_BITS],
+            suit: Suit::ALL[bit_i / SUIT_LANE_BITS],
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.0.count_ones() as usize;
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for CardSetIter {}
+
+pub const DEFAULT_PLAYERS: usize = 2;
+
+/// Rules deciding the deck and the order of combinations.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Variant {
+    #[default]
+    #[serde(rename = "h")]
+    Holdem,
+    // 36 cards from 6 to ace, ace plays below 6 in a straight (A-6-7-8-9) and a flush beats a full
+    // house, some rooms also rank three of a kind above a straight
+    #[serde(rename = "s")]
+    ShortDeck { trips_beat_straight: bool },
+}
+
+impl Variant {
+    pub fn lowest_rank(self) -> Rank {
+        match self {
+            Variant::Holdem => Rank::N2,
+            Variant::ShortDeck { .. } => Rank::N6,
+        }
+    }
+
+    /// Position of the combination kind from the weakest, same as the kind's order in hold'em.
+    pub fn kind_strength(self, kind: CombinationKind) -> u64 {
+        use CombinationKind::*;
+        match (self, kind) {
+            (Variant::ShortDeck { .. }, Flush) => FullHouse as u64,
+            (Variant::ShortDeck { .. }, FullHouse) => Flush as u64,
+            (
+                Variant::ShortDeck {
+                    trips_beat_straight: true,
+                },
+                ThreeOfAKind,
+            ) => Straight as u64,
+            (
+                Variant::ShortDeck {
+                    trips_beat_straight: true,
+                },
+                Straight,
+            ) => ThreeOfAKind as u64,
+            _ => kind as u64,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct Table {
     pub hand: Box<[Card]>,
     pub board: Box<[Option<Card>]>,
+    // number of players including me, each opponent holds a random hand
+    pub players: usize,
+    // hands the opponent can hold, any two remaining cards if not set
+    pub opponent_range: Option<Range>,
+    // cards known to be out of the deck, e.g. folded face-up by other players
+    pub dead: Box<[Card]>,
+    // hands of all opponents when known, solved as a showdown instead of against candidate hands
+    pub known_hands: Box<[[Card; 2]]>,
+    pub variant: Variant,
+}
+
+impl Default for Table {
+    fn default() -> Self {
+        Table {
+            hand: Default::default(),
+            board: vec![None; BOARD_SIZE].into_boxed_slice(),
+            players: DEFAULT_PLAYERS,
+            opponent_range: None,
+            dead: Default::default(),
+            known_hands: Default::default(),
+            variant: Default::default(),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -103,6 +379,135 @@ pub struct Solution {
     pub win_count: u64,
     #[serde(rename = "l")]
     pub lose_count: u64,
+    // aggregate against all opponents together, set only with more than one opponent
+    // (hands then still hold my results against each single opponent hand)
+    #[serde(rename = "m", default, skip_serializing_if = "Option::is_none")]
+    pub multiway: Option<MultiwaySolution>,
+    // set only when the counts come from random sampling instead of full enumeration
+    #[serde(rename = "a", default, skip_serializing_if = "Option::is_none")]
+    pub approximation: Option<Approximation>,
+    // count of board completions on which my final hand is each combination kind, indexed by
+    // `CombinationKind as usize` (not set in older precalculated solutions)
+    #[serde(rename = "k", default, skip_serializing_if = "Option::is_none")]
+    pub combination_counts: Option<[u64; COMBINATION_KIND_COUNT]>,
+}
+
+impl Solution {
+    /// Total counts of my wins, ties and losses against all candidate hands weighted by their
+    /// weight, or against all opponents together with more than one opponent.
+    pub fn outcome_counts(&self) -> (f64, f64, f64) {
+        if let Some(multiway) = &self.multiway {
+            return (
+                multiway.win_count as f64,
+                multiway.tie_count() as f64,
+                multiway.lose_count as f64,
+            );
+        }
+        self.hands.iter().fold(
+            (0.0, 0.0, 0.0),
+            |(win_count, tie_count, lose_count), hand| {
+                (
+                    win_count + hand.weight * hand.is_beaten_count as f64,
+                    tie_count + hand.weight * hand.tie_count as f64,
+                    lose_count + hand.weight * hand.beats_me_count as f64,
+                )
+            },
+        )
+    }
+
+    pub fn win_probability(&self) -> f64 {
+        let (win_count, tie_count, lose_count) = self.outcome_counts();
+        win_count / (win_count + tie_count + lose_count)
+    }
+
+    pub fn tie_probability(&self) -> f64 {
+        let (win_count, tie_count, lose_count) = self.outcome_counts();
+        tie_count / (win_count + tie_count + lose_count)
+    }
+
+    /// Expected share of the pot, with split pots divided between all tied players.
+    pub fn equity(&self) -> f64 {
+        if let Some(multiway) = &self.multiway {
+            return multiway.equity();
+        }
+        self.win_probability() + self.tie_probability() / 2.0
+    }
+
+    /// Probability of my final hand being each combination kind, indexed like `combination_counts`.
+    pub fn combination_probabilities(&self) -> Option<[f64; COMBINATION_KIND_COUNT]> {
+        let counts = self.combination_counts?;
+        let total: u64 = counts.iter().sum();
+        Some(counts.map(|count| count as f64 / total as f64))
+    }
+
+    /// Counts of candidate hands beating me by their combination kind, summed over all candidate
+    /// hands weighted by their weight, indexed like `combination_counts`.
+    pub fn beats_me_combination_counts(&self) -> [f64; COMBINATION_KIND_COUNT] {
+        let mut counts = [0.0; COMBINATION_KIND_COUNT];
+        for hand in self.hands.iter() {
+            for (count, &hand_count) in counts.iter_mut().zip(&hand.beats_me_combination_counts) {
+                *count += hand.weight * hand_count as f64;
+            }
+        }
+        counts
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Approximation {
+    // estimated pot share against a single opponent, weighted by hand weights
+    #[serde(rename = "e")]
+    pub equity: f64,
+    #[serde(rename = "s")]
+    pub equity_standard_error: f64,
+    // standard error of `MultiwaySolution::equity`, set only with more than one opponent
+    #[serde(rename = "m", default, skip_serializing_if = "Option::is_none")]
+    pub multiway_equity_standard_error: Option<f64>,
+}
+
+impl Approximation {
+    /// Bounds of the 95% confidence interval of the equity.
+    pub fn equity_bounds(&self) -> (f64, f64) {
+        // normal quantile of 97.5%
+        let margin = 1.96 * self.equity_standard_error;
+        (
+            (self.equity - margin).max(0.0),
+            (self.equity + margin).min(1.0),
+        )
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MultiwaySolution {
+    #[serde(rename = "o")]
+    pub opponents: u64,
+    // count of all board possibilities combined with all possible opponents' hands
+    #[serde(rename = "p")]
+    pub possibilities: u64,
+    #[serde(rename = "w")]
+    pub win_count: u64,
+    // tie_counts[i] is the count of ties with exactly i + 1 opponents (pot split i + 2 ways)
+    #[serde(rename = "t")]
+    pub tie_counts: Box<[u64]>,
+    #[serde(rename = "l")]
+    pub lose_count: u64,
+}
+
+impl MultiwaySolution {
+    pub fn tie_count(&self) -> u64 {
+        self.tie_counts.iter().sum()
+    }
+
+    /// Expected share of the pot, with split pots divided between all tied players.
+    pub fn equity(&self) -> f64 {
+        let tie_shares: f64 = self
+            .tie_counts
+            .iter()
+            .enumerate()
+            .map(|(i, &count)| count as f64 / (i + 2) as f64)
+            .sum();
+        (self.win_count as f64 + tie_shares) / self.possibilities as f64
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -113,9 +518,166 @@ pub struct HandSolution {
     pub beats_me_count: u64,
     #[serde(rename = "w")]
     pub is_beaten_count: u64,
+    // not set in older precalculated solutions, where it is the rest of board_possibilities
+    #[serde(rename = "t", default)]
+    pub tie_count: u64,
+    // weight of the hand in opponent's range
+    #[serde(
+        rename = "r",
+        default = "default_weight",
+        skip_serializing_if = "is_default_weight"
+    )]
+    pub weight: f64,
+    // beats_me_count split by the combination kind of the candidate hand, indexed by
+    // `CombinationKind as usize` (all zero in older precalculated solutions)
+    #[serde(rename = "k", default, skip_serializing_if = "is_zero_counts")]
+    pub beats_me_combination_counts: [u64; COMBINATION_KIND_COUNT],
+}
+
+fn is_zero_counts(counts: &[u64; COMBINATION_KIND_COUNT]) -> bool {
+    counts.iter().all(|&count| count == 0)
+}
+
+fn default_weight() -> f64 {
+    1.0
+}
+
+fn is_default_weight(weight: &f64) -> bool {
+    *weight == default_weight()
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RangeSolution {
+    #[serde(rename = "h")]
+    pub hands: Box<[RangeHandSolution]>,
+    // expected pot share of whole ranges, weighted by hand weights
+    #[serde(rename = "m")]
+    pub my_equity: f64,
+    #[serde(rename = "o")]
+    pub opponent_equity: f64,
+}
+
+// counts are summed over all board completions and opponent's hands, weighted by the weight of
+// opponent's hand
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RangeHandSolution {
+    #[serde(rename = "h")]
+    pub hand: Box<[Card]>,
+    #[serde(rename = "r")]
+    pub weight: f64,
+    #[serde(rename = "w")]
+    pub win_count: f64,
+    #[serde(rename = "t")]
+    pub tie_count: f64,
+    #[serde(rename = "l")]
+    pub lose_count: f64,
+}
+
+impl RangeHandSolution {
+    pub fn equity(&self) -> f64 {
+        (self.win_count + self.tie_count / 2.0)
+            / (self.win_count + self.tie_count + self.lose_count)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ShowdownSolution {
+    #[serde(rename = "b")]
+    pub board_possibilities: u64,
+    // my hand first, then the known hands in table order
+    #[serde(rename = "p")]
+    pub players: Box<[PlayerShowdown]>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PlayerShowdown {
+    #[serde(rename = "h")]
+    pub hand: Box<[Card]>,
+    #[serde(rename = "w")]
+    pub win_count: u64,
+    // split pots with any number of players
+    #[serde(rename = "t")]
+    pub tie_count: u64,
+    // expected share of the pot, with split pots divided between all tied players
+    #[serde(rename = "e")]
+    pub equity: f64,
 }
 
-#[derive(Copy, Clone, Debug, Ord, PartialOrd, Eq, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OutsSolution {
+    // sorted from the strongest combination I make
+    #[serde(rename = "g")]
+    pub groups: Box<[OutsGroup]>,
+    // sum of shares of all outs
+    #[serde(rename = "o")]
+    pub outs: f64,
+    // probability of hitting an out with the next card
+    #[serde(rename = "1")]
+    pub one_card_odds: f64,
+    // probability of hitting an out with at least one of the next two cards, set only on flop
+    #[serde(rename = "2", default, skip_serializing_if = "Option::is_none")]
+    pub two_card_odds: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OutsGroup {
+    #[serde(rename = "c")]
+    pub combination: CombinationKind,
+    // sorted from the highest share
+    #[serde(rename = "o")]
+    pub outs: Box<[Out]>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Out {
+    #[serde(rename = "c")]
+    pub card: Card,
+    // weighted share of opponent's hands that are ahead of me and that I win or tie against
+    // after this card, 1 against a single opponent's hand
+    #[serde(rename = "s")]
+    pub share: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NextCardsSolution {
+    // equity before the next card is dealt
+    #[serde(rename = "e")]
+    pub equity: f64,
+    // sorted from the best next card for me
+    #[serde(rename = "c")]
+    pub cards: Box<[NextCardSolution]>,
+}
+
+// counts are my totals against all opponent's hands (weighted by their weight in opponent's range),
+// or against all opponents together with more than one opponent
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NextCardSolution {
+    #[serde(rename = "c")]
+    pub card: Card,
+    #[serde(rename = "w")]
+    pub win_count: f64,
+    #[serde(rename = "t")]
+    pub tie_count: f64,
+    #[serde(rename = "l")]
+    pub lose_count: f64,
+    #[serde(rename = "e")]
+    pub equity: f64,
+    #[serde(rename = "f")]
+    pub effect: NextCardEffect,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NextCardEffect {
+    #[serde(rename = "i")]
+    Improves,
+    #[serde(rename = "n")]
+    Neutral,
+    #[serde(rename = "k")]
+    Kills,
+}
+
+// not ordered, the order of combinations depends on the variant, see `score_for`
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum Combination {
     HighCard([Rank; 5]),     // all cards, from highest
     Pair([Rank; 4]),         // rank of the pair, ranks of remaining 3 non-pair cards from highest
@@ -128,8 +690,45 @@ pub enum Combination {
     StraightFlush(Rank), // rank of highest card
 }
 
+pub const COMBINATION_KIND_COUNT: usize = 9;
+
+// combination without the ranks, in the same order as `Combination`, which is their hold'em order,
+// see `Variant::kind_strength`
+#[derive(EnumIter, Copy, Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum CombinationKind {
+    HighCard,
+    Pair,
+    TwoPairs,
+    ThreeOfAKind,
+    Straight,
+    Flush,
+    FullHouse,
+    FourOfAKind,
+    StraightFlush,
+}
+
 impl Combination {
+    pub fn kind(&self) -> CombinationKind {
+        match self {
+            Combination::HighCard(_) => CombinationKind::HighCard,
+            Combination::Pair(_) => CombinationKind::Pair,
+            Combination::TwoPairs(_) => CombinationKind::TwoPairs,
+            Combination::ThreeOfAKind(_) => CombinationKind::ThreeOfAKind,
+            Combination::Straight(_) => CombinationKind::Straight,
+            Combination::Flush(_) => CombinationKind::Flush,
+            Combination::FullHouse(_) => CombinationKind::FullHouse,
+            Combination::FourOfAKind(_) => CombinationKind::FourOfAKind,
+            Combination::StraightFlush(_) => CombinationKind::StraightFlush,
+        }
+    }
+
+    /// Score with hold'em order of combinations, higher score is a better combination.
     pub fn score(&self) -> u64 {
+        self.score_for(Variant::Holdem)
+    }
+
+    /// Score with the variant's order of combinations.
+    pub fn score_for(&self, variant: Variant) -> u64 {
         const WEIGHT_MULTIPLIER: u64 = RANK_COUNT as u64;
         const WEIGHTS: [u64; COMBINATION_SIZE] = [
             WEIGHT_MULTIPLIER.pow(5),
@@ -139,76 +738,24 @@ impl Combination {
             WEIGHT_MULTIPLIER,
         ];
         const COMBINATION_TYPE_WEIGHT: u64 = WEIGHT_MULTIPLIER.pow(6);
-        match self {
-            Combination::HighCard(ranks) => ranks
+        let ranks: &[Rank] = match self {
+            Combination::HighCard(ranks) | Combination::Flush(ranks) => ranks,
+            Combination::Pair(ranks) => ranks,
+            Combination::TwoPairs(ranks) | Combination::ThreeOfAKind(ranks) => ranks,
+            Combination::Straight(rank) | Combination::StraightFlush(rank) => {
+                std::slice::from_ref(rank)
+            }
+            Combination::FullHouse(ranks) | Combination::FourOfAKind(ranks) => ranks,
+        };
+        variant.kind_strength(self.kind()) * COMBINATION_TYPE_WEIGHT
+            + ranks
                 .iter()
                 .zip(WEIGHTS)
                 .map(|(&r, w)| w * r as u64)
-                .sum::<u64>(),
-            Combination::Pair(ranks) => {
-                1 * COMBINATION_TYPE_WEIGHT
-                    + ranks
-                        .iter()
-                        .zip(WEIGHTS)
-                        .map(|(&r, w)| w * r as u64)
-                        .sum::<u64>()
-            }
-            Combination::TwoPairs(ranks) => {
-                2 * COMBINATION_TYPE_WEIGHT
-                    + ranks
-                        .iter()
-                        .zip(WEIGHTS)
-                        .map(|(&r, w)| w * r as u64)
-                        .sum::<u64>()
-            }
-            Combination::ThreeOfAKind(ranks) => {
-                3 * COMBINATION_TYPE_WEIGHT
-                    + ranks
-                        .iter()
-                        .zip(WEIGHTS)
-                        .map(|(&r, w)| w * r as u64)
-                        .sum::<u64>()
-            }
-            Combination::Straight(rank) => {
-                4 * COMBINATION_TYPE_WEIGHT + (*rank) as u64 * WEIGHTS[0]
-            }
-            Combination::Flush(ranks) => {
-                5 * COMBINATION_TYPE_WEIGHT
-                    + ranks
-                        .iter()
-                        .zip(WEIGHTS)
-                        .map(|(&r, w)| w * r as u64)
-                        .sum::<u64>()
-            }
-            Combination::FullHouse(ranks) => {
-                6 * COMBINATION_TYPE_WEIGHT
-                    + ranks
-                        .iter()
-                        .zip(WEIGHTS)
-                        .map(|(&r, w)| w * r as u64)
-                        .sum::<u64>()
-            }
-            Combination::FourOfAKind(ranks) => {
-                7 * COMBINATION_TYPE_WEIGHT
-                    + ranks
-                        .iter()
-                        .zip(WEIGHTS)
-                        .map(|(&r, w)| w * r as u64)
-                        .sum::<u64>()
-            }
-            Combination::StraightFlush(rank) => {
-                8 * COMBINATION_TYPE_WEIGHT + (*rank) as u64 * WEIGHTS[0]
-            }
-        }
+                .sum::<u64>()
     }
 }
 
-#[derive(Default, Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
-pub struct ReducedCard {
-    pub is_flush: bool,
-    pub rank: Rank,
-}
-
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PrecalculatedSolution {
     #[serde(rename = "m")]
@@ -216,3 +763,54 @@ pub struct PrecalculatedSolution {
     #[serde(rename = "s")]
     pub solution: Solution,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    fn card_set_insert_remove() {
+        let card = Card {
+            rank: Rank::A,
+            suit: Suit::Clubs,
+        };
+        let mut set = CardSet::EMPTY;
+        assert!(set.insert(card));
+        assert!(!set.insert(card));
+        assert!(set.contains(card));
+        assert_eq!(set.len(), 1);
+        assert!(set.remove(card));
+        assert!(!set.remove(card));
+        assert!(set.is_empty());
+    }
+
+    #[rstest]
+    fn card_set_iterates_in_deck_order() {
+        let cards = Suit::ALL
+            .iter()
+            .flat_map(|&suit| Rank::ALL.iter().map(move |&rank| Card { rank, suit }))
+            .collect::<Vec<_>>();
+        let set: CardSet = cards.iter().rev().collect();
+        assert_eq!(set.len(), 52);
+        assert_eq!(set.iter().collect::<Vec<_>>(), cards);
+        assert!((!set).is_empty());
+    }
+
+    #[rstest]
+    fn card_set_from_rank_and_suit() {
+        let kings = CardSet::from(Rank::K);
+        let spades = CardSet::from(Suit::Spades);
+        assert_eq!(kings.len(), SUIT_COUNT);
+        assert_eq!(spades.len(), RANK_COUNT);
+        assert_eq!(
+            (kings & spades).iter().collect::<Vec<_>>(),
+            vec![Card {
+                rank: Rank::K,
+                suit: Suit::Spades
+            }]
+        );
+        assert_eq!(spades.suit_ranks(Suit::Spades), (1 << RANK_COUNT) - 1);
+        assert_eq!(kings.suit_ranks(Suit::Hearts), 1 << Rank::K as u16);
+    }
+}
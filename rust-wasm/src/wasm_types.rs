@@ -1,6 +1,12 @@
 use crate::types::{Rank, Suit};
-use crate::{types, Card, HandSolution, MaybeCard, Solution, Table};
+use crate::{
+    progress, types, Approximation, Card, CombinationCount, CombinationProbability, HandSolution,
+    MaybeCard, MultiwaySolution, NextCardSolution, NextCardsSolution, Out, OutsGroup, OutsSolution,
+    PlayerShowdown, Progress, RangeHandSolution, RangeSolution, ShowdownSolution, Solution, Table,
+    Variant,
+};
 use anyhow::{bail, Context, Ok, Result};
+use strum::IntoEnumIterator;
 
 pub fn from_wasm_table(table: &Table) -> Result<types::Table> {
     Ok(types::Table {
@@ -10,15 +16,61 @@ pub fn from_wasm_table(table: &Table) -> Result<types::Table> {
             .map(|c| Ok(from_wasm_maybe_card(c)?.context("hand cards must all be set, got blank")?))
             .collect::<Result<Vec<_>>>()?
             .into(),
-        board: table
-            .board
+        board: from_wasm_board(&table.board)?,
+        players: table.players,
+        opponent_range: table
+            .opponent_range
+            .as_deref()
+            .map(|range| range.parse().context("invalid opponent range"))
+            .transpose()?,
+        dead: table
+            .dead
             .iter()
-            .map(|c| Ok(from_wasm_maybe_card(c)?))
+            .map(|c| Ok(from_wasm_maybe_card(c)?.context("dead cards must all be set, got blank")?))
             .collect::<Result<Vec<_>>>()?
             .into(),
+        known_hands: table
+            .known_hands
+            .chunks(2)
+            .map(|hand| {
+                let cards = hand
+                    .iter()
+                    .map(|c| {
+                        Ok(from_wasm_maybe_card(c)?
+                            .context("known hand cards must all be set, got blank")?)
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                let [card1, card2] = cards[..] else {
+                    bail!("known hands must have 2 cards each")
+                };
+                Ok([card1, card2])
+            })
+            .collect::<Result<Vec<_>>>()?
+            .into(),
+        variant: from_wasm_variant(table.variant),
     })
 }
 
+pub fn from_wasm_variant(variant: Variant) -> types::Variant {
+    match variant {
+        Variant::Holdem => types::Variant::Holdem,
+        Variant::ShortDeck => types::Variant::ShortDeck {
+            trips_beat_straight: false,
+        },
+        Variant::ShortDeckTripsBeatStraight => types::Variant::ShortDeck {
+            trips_beat_straight: true,
+        },
+    }
+}
+
+pub fn from_wasm_board(board: &[MaybeCard]) -> Result<Box<[Option<types::Card>]>> {
+    Ok(board
+        .iter()
+        .map(from_wasm_maybe_card)
+        .collect::<Result<Vec<_>>>()?
+        .into())
+}
+
 fn from_wasm_maybe_card(card: &MaybeCard) -> Result<Option<types::Card>> {
     card.0
         .as_ref()
@@ -58,20 +110,173 @@ pub fn to_wasm_solution(solution: &types::Solution) -> Solution {
             .hands
             .iter()
             .map(|h| HandSolution {
-                hand: h
-                    .hand
-                    .iter()
-                    .map(|c| to_wasm_card(c))
-                    .collect::<Vec<_>>()
-                    .into(),
+                hand: h.hand.iter().map(to_wasm_card).collect::<Vec<_>>().into(),
                 beats_me_count: h.beats_me_count,
                 is_beaten_count: h.is_beaten_count,
+                tie_count: h.tie_count,
+                weight: h.weight,
+                beats_me_combinations: to_wasm_combination_counts(
+                    h.beats_me_combination_counts.map(|count| count as f64),
+                ),
             })
             .collect(),
         board_possibilities: solution.board_possibilities,
         win_count: solution.win_count,
         lose_count: solution.lose_count,
+        win_probability: solution.win_probability(),
+        tie_probability: solution.tie_probability(),
+        equity: solution.equity(),
+        multiway: solution.multiway.as_ref().map(|m| MultiwaySolution {
+            opponents: m.opponents,
+            possibilities: m.possibilities,
+            win_count: m.win_count,
+            tie_counts: m.tie_counts.clone(),
+            tie_count: m.tie_count(),
+            lose_count: m.lose_count,
+            equity: m.equity(),
+        }),
+        approximation: solution.approximation.as_ref().map(|a| {
+            let (equity_lower_bound, equity_upper_bound) = a.equity_bounds();
+            Approximation {
+                equity: a.equity,
+                equity_standard_error: a.equity_standard_error,
+                equity_lower_bound,
+                equity_upper_bound,
+                multiway_equity_standard_error: a.multiway_equity_standard_error,
+            }
+        }),
+        combination_probabilities: solution
+            .combination_probabilities()
+            .map(|probabilities| {
+                types::CombinationKind::iter()
+                    .zip(probabilities)
+                    .map(|(kind, probability)| CombinationProbability {
+                        combination: to_wasm_combination_kind(kind),
+                        probability,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default(),
+        beats_me_combinations: to_wasm_combination_counts(solution.beats_me_combination_counts()),
+    }
+}
+
+fn to_wasm_combination_counts(
+    counts: [f64; types::COMBINATION_KIND_COUNT],
+) -> Box<[CombinationCount]> {
+    types::CombinationKind::iter()
+        .zip(counts)
+        .filter(|&(_, count)| count != 0.0)
+        .map(|(kind, count)| CombinationCount {
+            combination: to_wasm_combination_kind(kind),
+            count,
+        })
+        .collect()
+}
+
+pub fn to_wasm_range_solution(solution: &types::RangeSolution) -> RangeSolution {
+    RangeSolution {
+        hands: solution
+            .hands
+            .iter()
+            .map(|h| RangeHandSolution {
+                hand: h.hand.iter().map(to_wasm_card).collect::<Vec<_>>().into(),
+                weight: h.weight,
+                win_count: h.win_count,
+                tie_count: h.tie_count,
+                lose_count: h.lose_count,
+                equity: h.equity(),
+            })
+            .collect(),
+        my_equity: solution.my_equity,
+        opponent_equity: solution.opponent_equity,
+    }
+}
+
+pub fn to_wasm_next_cards_solution(solution: &types::NextCardsSolution) -> NextCardsSolution {
+    NextCardsSolution {
+        equity: solution.equity,
+        cards: solution
+            .cards
+            .iter()
+            .map(|c| NextCardSolution {
+                card: to_wasm_card(&c.card),
+                win_count: c.win_count,
+                tie_count: c.tie_count,
+                lose_count: c.lose_count,
+                equity: c.equity,
+                effect: match c.effect {
+                    types::NextCardEffect::Improves => "improves".to_owned(),
+                    types::NextCardEffect::Neutral => "neutral".to_owned(),
+                    types::NextCardEffect::Kills => "kills".to_owned(),
+                },
+            })
+            .collect(),
+    }
+}
+
+pub fn to_wasm_showdown_solution(solution: &types::ShowdownSolution) -> ShowdownSolution {
+    ShowdownSolution {
+        board_possibilities: solution.board_possibilities,
+        players: solution
+            .players
+            .iter()
+            .map(|p| PlayerShowdown {
+                hand: p.hand.iter().map(to_wasm_card).collect::<Vec<_>>().into(),
+                win_count: p.win_count,
+                tie_count: p.tie_count,
+                equity: p.equity,
+            })
+            .collect(),
+    }
+}
+
+pub fn to_wasm_progress(progress: progress::Progress) -> Progress {
+    Progress {
+        fraction: progress.fraction,
+        elapsed_ms: progress.elapsed.as_secs_f64() * 1000.0,
+        remaining_ms: progress
+            .remaining
+            .map(|remaining| remaining.as_secs_f64() * 1000.0),
+    }
+}
+
+pub fn to_wasm_outs_solution(solution: &types::OutsSolution) -> OutsSolution {
+    OutsSolution {
+        groups: solution
+            .groups
+            .iter()
+            .map(|g| OutsGroup {
+                combination: to_wasm_combination_kind(g.combination),
+                outs: g
+                    .outs
+                    .iter()
+                    .map(|o| Out {
+                        card: to_wasm_card(&o.card),
+                        share: o.share,
+                    })
+                    .collect(),
+            })
+            .collect(),
+        outs: solution.outs,
+        one_card_odds: solution.one_card_odds,
+        two_card_odds: solution.two_card_odds,
+    }
+}
+
+pub fn to_wasm_combination_kind(kind: types::CombinationKind) -> String {
+    match kind {
+        types::CombinationKind::HighCard => "high_card",
+        types::CombinationKind::Pair => "pair",
+        types::CombinationKind::TwoPairs => "two_pairs",
+        types::CombinationKind::ThreeOfAKind => "three_of_a_kind",
+        types::CombinationKind::Straight => "straight",
+        types::CombinationKind::Flush => "flush",
+        types::CombinationKind::FullHouse => "full_house",
+        types::CombinationKind::FourOfAKind => "four_of_a_kind",
+        types::CombinationKind::StraightFlush => "straight_flush",
     }
+    .to_owned()
 }
 
 pub fn to_wasm_card(card: &types::Card) -> Card {
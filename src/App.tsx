@@ -10,23 +10,50 @@ import {useEffect, useState} from "react";
 import {AbortSignal} from "../rust-wasm/pkg";
 import type {Solution} from "./types/Solution.ts";
 import Stats from "./components/Stats.tsx";
+import type {Progress} from "./types/Progress.ts";
+import {type Variant, variantRanks} from "./types/Table.ts";
 
 function App() {
   const hand = useSelectedCards(2, 0)
   const board = useSelectedCards(5)
+  const [variant, setVariant] = useState<Variant>("holdem")
   const [solution, setSolution] = useState<Solution | null>(null)
   const [isLoading, setIsLoading] = useState(false)
+  const [progress, setProgress] = useState<Progress | null>(null)
   const [errorText, setErrorText] = useState<string | null>(null)
 
+  const changeVariant = (newVariant: Variant) => {
+    const ranks = variantRanks(newVariant)
+    const notInDeck = (card: Card) => !ranks.includes(card.rank)
+    hand.clearCards(notInDeck)
+    board.clearCards(notInDeck)
+    setVariant(newVariant)
+  }
+
   useEffect(() => {
     const cancellationToken = new AbortSignal()
     setIsLoading(true)
+    setProgress(null)
     setErrorText(null)
     const f = async () => {
       try {
         const solution = await solve(cancellationToken, {
           hand: hand.state.cards,
           board: board.state.cards,
+          players: 2,
+          opponentRange: null,
+          dead: [],
+          knownHands: [],
+          variant,
+        }, progress => {
+          if (!cancellationToken.aborted) {
+            setProgress(progress)
+          }
+        }, partialSolution => {
+          // the chart fills in while solving, and keeps the partial data when cancelled
+          if (!cancellationToken.aborted) {
+            setSolution(partialSolution)
+          }
         })
         if (cancellationToken.aborted) {
           console.log("solve: discarding stale solution", solution)
@@ -58,13 +85,20 @@ function App() {
   }, [
     hand.state.cards,
     board.state.cards,
+    variant,
   ])
 
   return (
     <>
       <div className="flex-container">
         <div style={{margin: "20px"}}>
-          <Selection onCardSelected={(card: Card) => {
+          <select value={variant} onChange={e => changeVariant(e.target.value as Variant)}>
+            <option value="holdem">Hold'em</option>
+            <option value="shortDeck">Short deck</option>
+            <option value="shortDeckTripsBeatStraight">Short deck, trips beat straight</option>
+          </select>
+          <br/>
+          <Selection variant={variant} onCardSelected={(card: Card) => {
             if (hand.setSelectedCard(card)) {
               board.setSelectedSlot(0)
             }
@@ -96,6 +130,7 @@ function App() {
       <WinrateChart
         solution={solution}
         isLoading={isLoading}
+        progress={progress}
         errorText={errorText}
       />
     </>
@@ -1,17 +1,20 @@
 import CardComponent from "./Card.tsx";
-import {type Card, ranks, suits} from "../types/Card.ts";
+import {type Card, suits} from "../types/Card.ts";
+import {type Variant, variantRanks} from "../types/Table.ts";
 
 export default function Selection(
   {
     onCardSelected,
+    variant,
   }: {
     onCardSelected: (card: Card) => void;
+    variant: Variant;
   }
 ) {
   let rows = []
   for (const [i, suit] of suits.entries()) {
     let cards = []
-    for (const [j, rank] of ranks.entries()) {
+    for (const [j, rank] of variantRanks(variant).entries()) {
       let card = {
         rank,
         suit,
@@ -1,11 +1,45 @@
 import styles from './Spinner.module.css'
+import type {Progress} from "../types/Progress.ts";
 
-export default function Spinner() {
+const circumference = 2 * Math.PI * 50
+
+// spins until the progress is known, then shows it as an arc with the remaining time
+export default function Spinner({progress}: { progress?: Progress | null }) {
+  if (!progress) {
+    return (<svg
+      width="120"
+      height="120"
+      viewBox="-60 -60 120 120"
+    >
+      <circle className={styles.spinner} cx="0" cy="0" r="50"/>
+    </svg>)
+  }
+  const done = circumference * progress.fraction
   return (<svg
     width="120"
     height="120"
     viewBox="-60 -60 120 120"
   >
-    <circle className={styles.spinner} cx="0" cy="0" r="50"/>
+    <circle className={styles.track} cx="0" cy="0" r="50"/>
+    <circle
+      className={styles.progress}
+      cx="0"
+      cy="0"
+      r="50"
+      strokeDasharray={`${done} ${circumference - done}`}
+    />
+    {
+      progress.remainingMs !== null && <text className={styles.remaining} x="0" y="0">
+        {formatRemaining(progress.remainingMs)}
+      </text>
+    }
   </svg>)
-}
\ No newline at end of file
+}
+
+function formatRemaining(ms: number): string {
+  const seconds = Math.ceil(ms / 1000)
+  if (seconds < 60) {
+    return `${seconds}s`
+  }
+  return `${Math.floor(seconds / 60)}m ${seconds % 60}s`
+}
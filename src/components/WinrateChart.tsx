@@ -2,15 +2,18 @@ import styles from './WinrateChart.module.css'
 import type {Solution} from "../types/Solution.ts";
 import Spinner from "./Spinner.tsx";
 import {cardToText} from "../types/Card.ts";
+import type {Progress} from "../types/Progress.ts";
 
 export default function WinrateChart(
   {
     solution,
     isLoading,
+    progress,
     errorText,
   }: {
     solution: Solution | null,
     isLoading: boolean,
+    progress: Progress | null,
     errorText: string | null
   }
 ) {
@@ -114,7 +117,7 @@ export default function WinrateChart(
   return <div className={styles.chart}>
     {
       isLoading && (<div className={styles.spinner}>
-        <Spinner/>
+        <Spinner progress={progress}/>
       </div>)
     }
     {
@@ -7,22 +7,29 @@ export const mock_solution: Solution = {
       hand: hand("7s2c"),
       beatsMeCount: 0,
       isBeatenCount: 5,
+      tieCount: 0,
     },
     {
       hand: hand("JhTh"),
       beatsMeCount: 4,
-      isBeatenCount: 1
+      isBeatenCount: 1,
+      tieCount: 0,
     },
     {
       hand: hand("AhAd"),
       beatsMeCount: 4,
-      isBeatenCount: 0
+      isBeatenCount: 0,
+      tieCount: 1,
     },
 
   ],
   boardPossibilities: 5,
   winCount: 6,
   loseCount: 8,
+  winProbability: 6 / 15,
+  tieProbability: 1 / 15,
+  equity: 6.5 / 15,
+  approximation: null,
 }
 
 type Hand = Card[]
@@ -16,6 +16,11 @@ export function useSelectedCards(count: number, initialSelectedSlot?: number) {
 
   const clearCardAt = (i: number) => setCardAt(i, null)
 
+  // e.g. cards that aren't in the deck of a newly selected variant
+  const clearCards = (shouldClear: (card: Card) => boolean) => {
+    setCards(cards.map(c => c !== null && shouldClear(c) ? null : c))
+  }
+
   // returns whether after selecting a card the selection is cleared (as last card was selected)
   const setSelectedCard = (card: Card | null): boolean => {
     if (selectedSlot === null) {
@@ -40,6 +45,7 @@ export function useSelectedCards(count: number, initialSelectedSlot?: number) {
     setSelectedSlot,
     setSelectedCard,
     clearCardAt,
+    clearCards,
   }
 }
 
@@ -0,0 +1,6 @@
+export interface Progress {
+  // between 0 and 1
+  fraction: number
+  elapsedMs: number
+  remainingMs: number | null
+}
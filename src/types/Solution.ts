@@ -5,10 +5,22 @@ export interface Solution {
   boardPossibilities: number
   winCount: number
   loseCount: number
+  winProbability: number
+  tieProbability: number
+  equity: number
+  // set when the solution is sampled instead of enumerated
+  approximation: Approximation | null
+}
+
+export interface Approximation {
+  // 95% confidence interval of the equity
+  equityLowerBound: number
+  equityUpperBound: number
 }
 
 export interface HandSolution {
   hand: Card[]
   beatsMeCount: number
   isBeatenCount: number
+  tieCount: number
 }
\ No newline at end of file
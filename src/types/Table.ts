@@ -1,6 +1,18 @@
-import type {Card} from "./Card.ts";
+import {type Card, type Rank, ranks} from "./Card.ts";
+
+// short deck uses 36 cards from 6 to ace, some rooms rank three of a kind above a straight
+export type Variant = "holdem" | "shortDeck" | "shortDeckTripsBeatStraight"
 
 export interface Table {
   hand: (Card | null)[]
   board: (Card | null)[]
-}
\ No newline at end of file
+  players: number
+  opponentRange: string | null
+  dead: Card[]
+  knownHands: Card[][]
+  variant: Variant
+}
+
+export function variantRanks(variant: Variant): readonly Rank[] {
+  return variant === "holdem" ? ranks : ranks.slice(ranks.indexOf("6"))
+}
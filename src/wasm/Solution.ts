@@ -1,10 +1,30 @@
-import type {Table} from "../types/Table.ts"
+import type {Table, Variant} from "../types/Table.ts"
 import type {Solution} from "../types/Solution.ts";
 import * as wasm from "../../rust-wasm/pkg";
 import type {Card, Rank, Suit} from "../types/Card.ts";
+import type {Progress} from "../types/Progress.ts";
+import {solveOnThreads, threadsEnabled} from "./Threads.ts";
 
-export default async function solve(cancellationToken: wasm.AbortSignal, table: Table): Promise<Solution> {
-  return fromWasmSolution(await wasm.solve(cancellationToken, toWasmTable(table)))
+// reuses the previous street's runouts when a board card is added
+let solver: wasm.Solver | null = null
+
+// onProgress is called at most every 100ms, or as shards finish when solving on threads,
+// onPartialSolution with provisional solutions at most every 250ms, not when solving on threads
+export default async function solve(
+  cancellationToken: wasm.AbortSignal,
+  table: Table,
+  onProgress?: (progress: Progress) => void,
+  onPartialSolution?: (solution: Solution) => void,
+): Promise<Solution> {
+  const unknownBoardCards = table.board.filter(c => c === null).length
+  // pre-flop is precalculated or sampled, turn and river are fast enough on one thread
+  if (threadsEnabled && unknownBoardCards >= 2 && unknownBoardCards < table.board.length && table.knownHands.length === 0) {
+    return fromWasmSolution(await solveOnThreads(cancellationToken, table, toWasmTable(table), onProgress))
+  }
+  solver ??= new wasm.Solver()
+  const progressCallback = onProgress && ((p: wasm.Progress) => onProgress(fromWasmProgress(p)))
+  const partialSolutionCallback = onPartialSolution && ((s: wasm.Solution) => onPartialSolution(fromWasmSolution(s)))
+  return fromWasmSolution(await solver.solve(cancellationToken, toWasmTable(table), progressCallback, partialSolutionCallback))
 }
 
 function toMaybeCard(c: Card | null): wasm.MaybeCard {
@@ -14,13 +34,29 @@ function toMaybeCard(c: Card | null): wasm.MaybeCard {
   return new wasm.MaybeCard(new wasm.Card(c.rank, c.suit))
 }
 
-function toWasmTable(t: Table): wasm.Table {
+export function toWasmTable(t: Table): wasm.Table {
   return new wasm.Table(
     t.hand.map(toMaybeCard),
     t.board.map(toMaybeCard),
+    t.players,
+    t.opponentRange ?? undefined,
+    t.dead.map(toMaybeCard),
+    t.knownHands.flat().map(toMaybeCard),
+    toWasmVariant(t.variant),
   )
 }
 
+function toWasmVariant(v: Variant): wasm.Variant {
+  switch (v) {
+    case "holdem":
+      return wasm.Variant.Holdem
+    case "shortDeck":
+      return wasm.Variant.ShortDeck
+    case "shortDeckTripsBeatStraight":
+      return wasm.Variant.ShortDeckTripsBeatStraight
+  }
+}
+
 function fromWasmSolution(s: wasm.Solution): Solution {
   return {
     hands: s.hands.map(h => {
@@ -28,10 +64,26 @@ function fromWasmSolution(s: wasm.Solution): Solution {
         hand: h.hand.map(c => ({rank: c.rank as Rank, suit: c.suit as Suit})),
         beatsMeCount: Number(h.beats_me_count),
         isBeatenCount: Number(h.is_beaten_count),
+        tieCount: Number(h.tie_count),
       }
     }),
     boardPossibilities: Number(s.board_possibilities),
     winCount: Number(s.win_count),
     loseCount: Number(s.lose_count),
+    winProbability: s.win_probability,
+    tieProbability: s.tie_probability,
+    equity: s.equity,
+    approximation: s.approximation ? {
+      equityLowerBound: s.approximation.equity_lower_bound,
+      equityUpperBound: s.approximation.equity_upper_bound,
+    } : null,
+  }
+}
+
+function fromWasmProgress(p: wasm.Progress): Progress {
+  return {
+    fraction: p.fraction,
+    elapsedMs: p.elapsed_ms,
+    remainingMs: p.remaining_ms ?? null,
   }
 }
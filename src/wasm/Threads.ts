@@ -0,0 +1,82 @@
+import type {Table} from "../types/Table.ts"
+import type {Progress} from "../types/Progress.ts";
+import * as wasm from "../../rust-wasm/pkg";
+
+// Each worker runs its own wasm instance and solves a share (shard) of the runouts, the counts
+// are merged on the main thread. Shared wasm memory would need cross-origin isolation headers,
+// which the static hosting can't set, see the README for this deviation from a wasm threads build.
+// Merged solutions are cached like solutions solved on the main thread, and progress is reported
+// as shards finish. There are no provisional solutions, and the incremental solver doesn't get
+// the runouts, so the next street is solved from scratch.
+
+export type WorkerRequest =
+  | { type: "solve", id: number, table: Table, shard: number, shards: number }
+  | { type: "cancel", id: number }
+
+export type WorkerResponse =
+  | { id: number, counts: string }
+  | { id: number, error: string }
+
+// optional build: VITE_WASM_THREADS=true npm run build
+export const threadsEnabled = import.meta.env.VITE_WASM_THREADS === "true"
+  && typeof Worker !== "undefined"
+  && navigator.hardwareConcurrency > 1
+
+let workers: Worker[] | null = null
+let nextId = 0
+
+export async function solveOnThreads(
+  cancellationToken: wasm.AbortSignal,
+  table: Table,
+  wasmTable: wasm.Table,
+  onProgress?: (progress: Progress) => void,
+): Promise<wasm.Solution> {
+  const cached = wasm.cached_solution(wasmTable)
+  if (cached) {
+    return cached
+  }
+  workers ??= Array.from(
+    {length: navigator.hardwareConcurrency},
+    () => new Worker(new URL("./worker.ts", import.meta.url), {type: "module"}),
+  )
+  const id = nextId++
+  const start = performance.now()
+  let finishedShards = 0
+  const shards = workers.map((worker, shard, all) => new Promise<string>((resolve, reject) => {
+    const onMessage = (e: MessageEvent<WorkerResponse>) => {
+      if (e.data.id !== id) {
+        return
+      }
+      worker.removeEventListener("message", onMessage)
+      if ("error" in e.data) {
+        reject(e.data.error)
+      } else {
+        finishedShards++
+        onProgress?.(shardsProgress(finishedShards / all.length, performance.now() - start))
+        resolve(e.data.counts)
+      }
+    }
+    worker.addEventListener("message", onMessage)
+    worker.postMessage({type: "solve", id, table, shard, shards: all.length} satisfies WorkerRequest)
+  }))
+  // settles the wait below when the solve ends without being cancelled
+  const finished = new wasm.AbortSignal()
+  cancellationToken.wait(finished).then(aborted => {
+    if (aborted) {
+      workers?.forEach(worker => worker.postMessage({type: "cancel", id} satisfies WorkerRequest))
+    }
+  })
+  try {
+    return wasm.merge_shards(wasmTable, await Promise.all(shards))
+  } finally {
+    finished.abort()
+  }
+}
+
+function shardsProgress(fraction: number, elapsedMs: number): Progress {
+  return {
+    fraction,
+    elapsedMs,
+    remainingMs: elapsedMs * (1 - fraction) / fraction,
+  }
+}
@@ -0,0 +1,43 @@
+import init, * as wasm from "../../rust-wasm/pkg/rust_wasm.js"
+import {toWasmTable} from "./Solution.ts";
+import type {WorkerRequest, WorkerResponse} from "./Threads.ts";
+
+const initialized = init()
+// solves received but not finished yet
+const pending = new Set<number>()
+const cancelled = new Set<number>()
+const signals = new Map<number, wasm.AbortSignal>()
+
+function respond(response: WorkerResponse) {
+  self.postMessage(response)
+}
+
+self.onmessage = async (e: MessageEvent<WorkerRequest>) => {
+  const request = e.data
+  if (request.type === "cancel") {
+    if (signals.has(request.id)) {
+      signals.get(request.id)!.abort()
+    } else if (pending.has(request.id)) {
+      // solve is still waiting for the wasm instance
+      cancelled.add(request.id)
+    }
+    return
+  }
+  pending.add(request.id)
+  await initialized
+  const signal = new wasm.AbortSignal()
+  if (cancelled.has(request.id)) {
+    signal.abort()
+  }
+  signals.set(request.id, signal)
+  try {
+    const counts = await wasm.solve_shard(signal, toWasmTable(request.table), request.shard, request.shards)
+    respond({id: request.id, counts})
+  } catch (e) {
+    respond({id: request.id, error: String(e)})
+  } finally {
+    pending.delete(request.id)
+    cancelled.delete(request.id)
+    signals.delete(request.id)
+  }
+}
@@ -5,6 +5,10 @@ import {viteStaticCopy} from "vite-plugin-static-copy";
 // https://vite.dev/config/
 export default defineConfig({
   "base": "/poker-winrate/",
+  // solve workers import the wasm package as a module
+  worker: {
+    format: "es",
+  },
   plugins: [
     react(),
     viteStaticCopy({